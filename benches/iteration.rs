@@ -0,0 +1,40 @@
+//! Throughput of the chaos game's core loop, timed by the binary's `bench` command so formatting
+//! and I/O are left out. Reports the median of a few runs in points per second.
+
+use std::process::Command;
+
+const RUNS: usize = 5;
+const ITERATIONS: usize = 10_000_000;
+
+/// Runs `chaos-game bench` once and returns the points per second it reports
+fn points_per_second() -> f64 {
+    let output = Command::new(env!("CARGO_BIN_EXE_chaos-game"))
+        .args([
+            "bench",
+            "--seed",
+            "1",
+            "--iterations",
+            &ITERATIONS.to_string(),
+        ])
+        .output()
+        .expect("Couldn't run chaos-game");
+    assert!(output.status.success(), "chaos-game bench failed");
+    String::from_utf8(output.stdout)
+        .expect("Output should be UTF-8")
+        .lines()
+        .find_map(|line| line.strip_prefix("points/sec: "))
+        .expect("No points/sec in the output")
+        .parse()
+        .expect("points/sec should be a number")
+}
+
+fn main() {
+    let mut runs: Vec<f64> = (0..RUNS).map(|_| points_per_second()).collect();
+    runs.sort_by(|a, b| a.partial_cmp(b).expect("Throughput shouldn't be NaN"));
+    println!(
+        "sierpinski-triangle: {:.1} million points/sec (median of {} runs of {} iterations)",
+        runs[RUNS / 2] / 1e6,
+        RUNS,
+        ITERATIONS
+    );
+}
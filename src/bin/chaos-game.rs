@@ -7,12 +7,577 @@
 /// The following will write the points of a sierpinski triangle to plots/sierpinski-triangle.txt
 /// `chaos-game sierpinski-triangle > plots/sierpinski-triangle.txt`
 ///
+/// ### Finding a fractal
+/// `chaos-game list` prints the name of every fractal and `chaos-game describe vicsek` explains
+/// how one is generated.
+///
+/// ### Subdivision curves
+/// `chaos-game subdivide --rule koch --depth 4` prints the Koch snowflake as a closed path for
+/// `plot '...' with lines`, replacing every edge with the rule's shape `--depth` times (4 by
+/// default). The rules are `koch`, `cesaro`, and `minkowski`. `--vertices-file PATH` starts from
+/// the points in a file instead of a triangle, taken counterclockwise so the shapes point out,
+/// and `--open` leaves out the edge joining the last point back to the first.
+///
+/// ### Pascal's triangle
+/// `chaos-game pascal --rows 64` prints a point for every odd number in the first 64 rows of
+/// Pascal's triangle, which makes an exact Sierpinski triangle without any randomness.
+///
+/// ### Rose curves
+/// `chaos-game rose --k 4 --points 1000` prints 1000 points spaced evenly around the rose
+/// `r = cos(k theta)`, which has k petals for odd k and 2k for even k (4 and 1000 by default).
+/// Fractional k like 1.5 works too, going round as many turns as it takes the curve to close.
+/// Plot it `with lines` for a smooth curve.
+///
+/// ### Apollonian gasket
+/// `chaos-game apollonian --depth 5` prints points around the circles of an Apollonian gasket,
+/// nested circles filling the gaps between circles, filling `--depth` levels of gaps (4 by
+/// default, 12 at most). `--points N` sets how many points go around the outer circle (1000 by default), with
+/// smaller circles getting fewer.
+///
+/// ### Hilbert curve
+/// `chaos-game hilbert --order 5` prints the 4^5 points of the order 5 Hilbert curve in the order
+/// it visits them, a unit apart, so `plot '...' with lines` draws the curve (order 5 by default).
+///
+/// ### Penrose tiling
+/// `chaos-game penrose --depth 5` prints the edges of a Penrose tiling of thin and thick rhombi,
+/// made by cutting a wheel of triangles into smaller ones in the golden ratio `--depth` times (4
+/// by default, 14 at most). Each edge is two points followed by a blank line, for
+/// `plot '...' with lines`.
+///
+/// ### Medial polygons
+/// `chaos-game medial --medial-depth 4` prints a triangle and the polygons inside it made by
+/// joining the midpoints of its edges, and of that polygon's edges, 4 times over (4 by default),
+/// which is the shrinking the chaos game's halfway jumps are built on. `--vertices-file PATH`
+/// starts from the polygon in a file instead. Each polygon is closed and followed by a blank line,
+/// with a group column giving its level from 0 for the outermost, for `plot '...' using 1:2:3 with
+/// lines lc variable`.
+///
+/// ### Benchmarking
+/// `chaos-game bench --iterations N` times N rounds of the Sierpinski triangle at a fixed seed
+/// without writing the points and prints the throughput in points per second. `cargo bench` runs
+/// it a few times in a release build and reports the median.
+///
+/// ### Merging runs
+/// `chaos-game merge a.txt b.txt --out merged.txt --dedup-epsilon 0.01` concatenates point files,
+/// e.g. from runs with different seeds. `--dedup-epsilon E` drops any point within E of one
+/// already kept, `0` drops exact repeats only. The output flags work as they do when generating.
+///
 /// ### Viewing the fractal with gnuplot
 /// `plot 'plots/sierpinski-triangle.txt' with points`
 ///
+/// ### Heatmaps with gnuplot
+/// `chaos-game --histogram 512 > counts.txt` counts the points in each cell of a 512 x 512 grid
+/// over the fractal's frame, for `plot 'counts.txt' using 1:2:3 with image`. Large grids load
+/// much faster with `--histogram-binary` added, which writes the counts as gnuplot's binary
+/// matrix of 32 bit floats: `plot 'counts.bin' binary matrix with image`.
+///
 /// ### Animation of the fractal with gnuplot
 /// `do for [i=0;1000000] { plot 'plots/vicsek.txt' every ::0::i }`
+///
+/// Output is buffered, so to watch a file fill in while it's still being written add
+/// `--flush-every N` to have every N points reach the file as they're generated.
+///
+/// To watch it draw in real time pipe it straight into gnuplot slowed down with `--rate N`, e.g.
+/// `chaos-game --rate 2000 | gnuplot -p -e "plot '-' with dots"` (gnuplot only draws when the
+/// input ends, so a `replot` loop on a file written with `--rate` suits longer runs).
+///
+/// # Options
+/// `CHAOS_ITERATIONS` and `CHAOS_JUMP_DISTANCE` in the environment stand in for `--iterations`
+/// and `--jump-distance` when the flags aren't given, e.g. in containers. A flag always wins over
+/// the environment, which wins over the defaults.
+///
+/// `--quiet` suppresses all diagnostic output on stderr, leaving only the points on stdout. Hard
+/// errors are still reported.
+///
+/// `--sides N` sets the number of vertices for `nflake` (defaults to 5, the pentaflake).
+///
+/// `--theta-offset DEG` turns the regular polygon `nflake`, `sierpinski-octagon`, and `hexaflake`
+/// are built on counterclockwise by DEG degrees, moving the first vertex from directly right of
+/// the center, e.g. `--theta-offset 90` to stand a pentaflake on its flat side pointing up.
+///
+/// `--iterations N` sets how many points are generated (defaults to 1000000).
+///
+/// `--auto-iterations` fits the number of points to the polygon fractals' shape instead, since
+/// more vertices make more, smaller copies that take longer to fill in. It's the default million
+/// for a triangle scaled by the square of the vertex count over 3, e.g. about 1.8 million for
+/// square-one and 2.8 million for the pentaflake, and the count picked is reported on stderr.
+/// `--iterations` wins when both are given.
+///
+/// `--until-coverage P` keeps generating until points have landed in P of the cells, e.g. `0.3`
+/// for 30%, of a 256 by 256 grid over the box around the shape the fractal is built on, and then
+/// stops. Fractals leave most of the box empty, so check what a long run reaches before picking
+/// P. `--iterations` caps how long it tries, 100000000 by default, and the coverage reached is
+/// reported on stderr.
+///
+/// `--rotate-x DEG`, `--rotate-y DEG`, and `--rotate-z DEG` pre-rotate the 3D
+/// `sierpinski-tetrahedron` in that order, so it can be plotted with `splot` without fiddling with
+/// `set view`.
+///
+/// `--depth-shade` adds a fourth column to `sierpinski-tetrahedron` running from 0 at its lowest
+/// point to 1 at its highest, after any rotation, so `splot '...' using 1:2:3:4 with points
+/// palette` shades it by depth and the nested tetrahedra stand out.
+///
+/// `--jump-distance D` overrides how far towards the chosen vertex each jump goes for the polygon
+/// fractals, 0 staying put and 1 landing on the vertex. It takes a decimal or an exact fraction,
+/// e.g. `--jump-distance 2/3` rather than `0.6667`. It has to be more than 0 and less than 1,
+/// since beyond 1 the points overshoot and usually fly off, unless `--allow-extrapolation` is
+/// given too. A comma separated list cycles through its distances one jump after another, e.g.
+/// `--jump-distance 1/2,2/5` alternates between contracting by a half and by three fifths.
+///
+/// `--jump-distances D:P,...` draws each jump's distance at random instead of cycling, distance
+/// `D` with probability in proportion to `P`, e.g. `--jump-distances 1/2:0.7,0.4:0.3` jumps
+/// halfway seven times in ten and four tenths of the way the rest. The distances are checked the
+/// same as `--jump-distance`'s, the weights have to be more than 0, and it can't be combined with
+/// `--jump-distance`.
+///
+/// `--value-column index|distance|vertex` adds a third column to color by with gnuplot's
+/// `palette`: the iteration, the length of the jump that landed on the point, or the index of the
+/// vertex jumped towards (-1 for the starting point). Output is two columns without it.
+///
+/// `--color vertex|iteration|radius` adds a column with an RGB color for each point of the polygon
+/// fractals, for `plot '...' using 1:2:3 with points lc rgb variable`: one color per vertex jumped
+/// towards, a fade over the run, or a fade out from the centroid. It comes after the
+/// `--value-column` column when both are given.
+///
+/// `--color-palette viridis|magma|grayscale|rainbow` picks the gradient those colors come from.
+/// It's viridis by default, which stays even to the eye and readable in grayscale.
+///
+/// `--quantize-colors N` rounds those colors to N evenly spaced along the palette and writes the
+/// index of each point's color, 0 to N-1 (-1 for an uncolored starting point), in place of the
+/// RGB value, which keeps large colored clouds compact. The palette is written once at the top as
+/// `# color INDEX #RRGGBB` rows, which gnuplot skips as comments. Tools that take palette indices
+/// can read it from there, and gnuplot can use it as `set palette defined (0 '#440154', ...)` with
+/// `set cbrange [0:N-1]` and `set palette maxcolors N` to plot `using 1:2:3 with dots lc palette`.
+///
+/// `--sample K` keeps a uniformly random sample of K of the generated points and prints only
+/// those, in generation order, once the run is done. Output stays at K lines however large
+/// `--iterations` is.
+///
+/// `--every N` writes only every Nth point generated, starting with the first, for a sparser
+/// preview that still covers the whole fractal. The game plays all `--iterations` either way. N
+/// has to be at least 1. `--convergence` rows are thinned the same way, keeping those for every
+/// Nth iteration.
+///
+/// `--decimate-adaptive K` thins the points by density instead: a 128 x 128 grid is laid over the
+/// shape the fractal is built on, and a point landing in a cell that's already had c points is
+/// kept with probability K/c. Sparse edges and faint structure keep nearly all their points while
+/// the dense core is thinned hard, so it shrinks the output without erasing detail the way
+/// `--every` does. Each cell keeps roughly K(1 + ln(c/K)) of its c points.
+///
+/// `--grid-unique RES` writes at most one point per RES x RES cell of a grid aligned with the
+/// origin, the first to land in it, dropping the rest wherever they fall. Output is bounded by the
+/// number of cells the fractal touches, which suits rasterizing: `--grid-unique 1 --integer`
+/// writes each pixel once. RES is in the fractal's own units, before `--scale`, and it applies
+/// after `--tile` and the transforms. Memory grows with the number of occupied cells, a few dozen
+/// bytes each, rather than with the iterations.
+///
+/// `--clip-to-polygon` drops any point outside the polygon the fractal is built on, e.g. ones
+/// pushed out by `--jump-distance` above 1 or `--orbit`. Points on its edges are kept. `--stats`
+/// reports how many were dropped. Shapes made of several polygons, like `triangle-in-square`, clip
+/// to the convex hull around them. It's an error for fractals that aren't built on a polygon.
+///
+/// `--output PATH` writes the points to PATH instead of stdout, replacing whatever was there.
+/// Add `--append` to add to the end of the file instead, e.g. to build one cloud out of several
+/// runs.
+///
+/// `--output tcp://HOST:PORT` or `--output unix://PATH` connects to a TCP or Unix domain socket
+/// and streams the points over it as they're generated, e.g. to a separate renderer drawing
+/// them live. The protocol is the same plain text as a file: one point per line as
+/// space-separated values ending in `\n`, with the same extra columns, blank rows, and `#`
+/// comment rows, and the connection is closed once the run is done. Rows are buffered, so add
+/// `--flush-every` (or `--rate`) to have them sent promptly. The tool exits with an error if it
+/// can't connect or the reader hangs up.
+///
+/// `--hull` prints only the convex hull of the generated points, counterclockwise and closed by
+/// repeating the first vertex, for `plot '...' with lines`. Every point is held in memory until
+/// the run is done.
+///
+/// `--with-hull` writes the points as usual followed by their convex hull, to show the attractor
+/// inside it in one plot. The points get a group column of 0, then after a blank row the hull is
+/// written counterclockwise and closed with a group of 1, e.g. `plot '...' every :::0::0 with dots,
+/// '' every :::1 with lines` or `using 1:2:3 with dots lc variable`. Every point is held in
+/// memory until the run is done. It can't be combined with flags that add their own columns or
+/// hold the rows back.
+///
+/// `--z-order` writes the points sorted along the
+/// [Z-order curve](https://en.wikipedia.org/wiki/Z-order_curve) through the box around them
+/// rather than in the order they were generated, so points close together in the plane are mostly
+/// close together in the file, e.g. for building a quadtree. Every point is held in memory until
+/// the run is done.
+///
+/// `--histogram N` writes how many points landed in each cell of an N x N grid over the shape
+/// the fractal is built on instead of the points, as `x y count` rows giving each cell's center.
+/// `--histogram-binary` writes the counts as gnuplot's binary matrix instead (see Heatmaps above).
+///
+/// `--tile ROWS COLS` repeats the fractal in a grid, each copy offset by the width and height of
+/// the shape it's built on. A group column numbering the copies row by row is added so they can
+/// be colored separately.
+///
+/// `--panels A,B,C` plays several fractals with the same options and lays them out side by side
+/// in a grid of panels, `--cols N` to a row (as close to square as it gets by default), for
+/// comparison figures. Every panel is the same size, fitting the largest fractal with a 10% gap,
+/// and the first row is at the top. A group column numbering the panels in order is added, e.g.
+/// `chaos-game --panels sierpinski-triangle,vicsek,nflake --cols 2` plotted with
+/// `plot '...' using 1:2:3 with dots lc variable`. The output flags apply to the whole grid.
+/// The fractals come from the list alone, so a fractal name can't be given as well.
+///
+/// `--format polar` writes each point as `theta r` instead of `x y`, measured from the centroid
+/// of the vertices the fractal is built on with theta in radians. View it with
+/// `set polar; plot 'plots/polar.txt' using 1:2 with points`. `--format cartesian` is the default.
+///
+/// `--scale S` multiplies the coordinates written by S, and `--integer` rounds them to the nearest
+/// whole number, halves away from zero, e.g. `--scale 10.24 --integer` for pixel coordinates on a
+/// 1024 pixel grid over a 100 wide fractal. With `--format polar` they apply to r and not theta.
+///
+/// `--sig-figs N` writes coordinates to N significant figures rather than in full, so tiny and
+/// huge values keep the same relative detail, e.g. `--sig-figs 3` writes 0.000123 and 12300.
+/// Values below 0.00001 or from 10^15 up are written in scientific notation like `1.23e-7`.
+/// `--integer` takes precedence.
+///
+/// `--delta` writes each point as `dx dy`, its offset from the point written before it, with the
+/// first point written as is. That's how pen plotters like their moves and it compresses better,
+/// but the coordinates have to be summed back up to plot it, e.g. with gnuplot's
+/// `plot 'deltas.txt' using (x = x + $1):(y = y + $2)` after `x = 0; y = 0`. Offsets are taken
+/// after `--scale` and `--integer`, so with `--integer` the sums land exactly on the rounded
+/// points. It can't be combined with `--sample`, `--split`, or `--threads`.
+///
+/// `--points-per-line K` packs K points onto each line, separated by spaces, instead of one per
+/// line, for batch tools that read a fixed number of points per record and for gnuplot plots that
+/// pick the points out by column, e.g. `plot 'packed.txt' using 1:2, '' using 3:4` with K of 2.
+/// Extra columns stay with their point. The last line is short when K doesn't divide the number
+/// of points, and `--resume-from` can't read a packed file. It can't be combined with `--split`.
+///
+/// `--rate N` writes at most N rows a second, sleeping between them and flushing before each
+/// sleep, so the fractal appears to draw itself when watched live. It slows the run down
+/// drastically by design: a million points at `--rate 1000` take over 16 minutes.
+///
+/// `--flush-every N` flushes the output each time N more rows have been written. Without it rows
+/// are written in 8 KiB blocks (and all at once at the end), which is fastest but means a file
+/// being watched only grows a block at a time. Smaller N is more responsive and slower.
+///
+/// `--seed N` seeds the random number generator so the same command prints the same points.
+/// Without it a seed is drawn from the OS's entropy source and logged on stderr as `seed: N`, so
+/// any run can be replayed by passing that seed back. The same seed, options, and build of
+/// chaos-game always print the same points; a different version may not, if it changes how the
+/// seed is used.
+///
+/// `--threads N` splits the iterations between N games played in parallel and writes their points
+/// one game after another. Each game gets its own seed derived from `--seed`, so the same seed
+/// and thread count print the same points. It can't be combined with `--hull`, `--with-hull`,
+/// `--convergence`, `--stats`, `--nn-stats`, `--selection-histogram`, `--auto-iterations`,
+/// `--summary-only`, `--gif`, `--svg`, `--z-order`, `--histogram`, `--delta`, `--until-coverage`,
+/// `--decimate-adaptive`, `--cell-count-check`, `--verify`, `--emit-selection`, or `--meta`,
+/// which all need to see the whole run as one game.
+///
+/// `--restrict-expr EXPR` has the polygon fractals pick targets uniformly at random but turn down
+/// any candidate for which EXPR is 0, replacing the fractal's own rule. EXPR is integer arithmetic
+/// over `p`, the index of the previous target, `c`, the candidate's, and `n`, the number of
+/// targets, with `+ - * / %`, `abs(...)`, comparisons, `&& || !`, and parentheses. Comparisons
+/// are 1 when true and 0 when false, and `%` is never negative. E.g. `square-one` is
+/// `--restrict-expr 'c != p'` on a square, and `--restrict-expr '(c - p) % n != 2'` never jumps to
+/// the vertex two along. The first pick is unrestricted. A rule that turns down every target after
+/// some previous one can't be satisfied and is an error before anything is generated.
+///
+/// `--target-set vertices|medial|augmented` has the polygon fractals jump towards a target picked
+/// uniformly from the polygon's vertices, the midpoints of its edges, or both, in place of the
+/// fractal's own targets and any restriction on picking them. In `--value-column vertex` the
+/// augmented set is numbered around the polygon, even numbers being vertices and odd ones
+/// midpoints.
+///
+/// `--start-weights W1,W2,...` picks the starting point of the polygon fractals in proportion to
+/// the weights instead of uniformly. The game starts from the midpoint of one of the polygon's
+/// edges, the first weight going to the edge from the first vertex to the second and so on.
+/// Mostly useful for studying the first few jumps before the point settles onto the fractal. A
+/// count that doesn't match the edges, or the flag on a fractal without a polygon, is an error.
+///
+/// `--resume-from PATH` starts the polygon fractals from the point on the last line of a file
+/// they wrote earlier rather than a midpoint, and writes the points after it, e.g. `--resume-from
+/// cloud.txt --output cloud.txt --append` to extend a cloud. Only the position is resumed, not the
+/// generator: with the earlier run's `--seed` the same targets are picked again from the new
+/// position, so give it a different seed to carry on as one longer run would.
+///
+/// `--emit-selection PATH` writes the index of every target the polygon fractals pick to PATH,
+/// one per line in order, alongside the usual points. With `--seed` it gives a reproducible trace
+/// for checking that a rule like square-one's never picking the same vertex twice holds.
+///
+/// `--selection-histogram` counts how many times the polygon fractals pick each target and
+/// reports the counts on stderr once the run is done, one `target N: COUNT (P%)` line per target
+/// with `--orbit`'s moving target last. Uniform picks should come out about even, while
+/// `--restrict-expr` and rules like square-one's skew them.
+///
+/// `--orbit RADIUS,SPEED` adds a moving target to the polygon fractals: a point circling the
+/// centroid at RADIUS, turning SPEED radians every iteration. It's picked as often as any single
+/// vertex and shows up as one past the last vertex in `--value-column vertex`.
+///
+/// `--max-points N` stops writing after N point rows, however many iterations were asked for.
+/// When points are written as they're generated the game stops there too. Reaching the limit is
+/// reported on stderr.
+///
+/// `--split N` spreads the rows over N files named after `--output` with a numbered suffix, e.g.
+/// `--output out.txt --split 2` writes `out.txt.000` and `out.txt.001`. Rows are dealt out in
+/// turn, so each file holds every Nth row in generation order and reading the files round-robin
+/// gives back the original order. Header lines are repeated in every file.
+///
+/// `--exact` tracks each point of the midpoint fractals with exact fractions instead of floats,
+/// so rounding doesn't build up. It needs a build with `--features exact`, is much slower, and
+/// can only run about 120 iterations before the fractions overflow.
+///
+/// `--gif PATH` draws the points into an animated GIF instead of printing them, one frame every
+/// `--iterations` / `--frames` points (60 frames by default) so it shows the fractal filling in,
+/// e.g. `chaos-game sierpinski-triangle --gif sierpinski.gif --frames 60`. It needs a build with
+/// `--features gif`.
+///
+/// `--svg PATH` draws the points as dots in an SVG image instead of printing them, framed by the
+/// shape the fractal is built on. `--svg-radius R` sets the dot radius in the fractal's own units
+/// (a 500th of the frame by default), `--svg-color C` their fill (`#222222`), and
+/// `--svg-background C` the page (`white`). Colors are anything SVG accepts, e.g. `navy` or
+/// `#ff8800`.
+///
+/// `--stats` reports the range, median, and quartiles of the x and y coordinates on stderr once
+/// the run is done. The quartiles are streaming estimates so the points don't have to be kept.
+///
+/// `--nn-stats` reports how far each point is from its nearest neighbor on stderr once the run is
+/// done: the smallest, largest, mean, median, and quartile distances. A small spread means the
+/// fractal is filled in evenly. To keep it quick it's measured over a uniform random sample of
+/// 10000 of the points rather than all of them. The distances are between sampled points, so
+/// they're wider than the gaps in the whole cloud and stop shrinking once `--iterations` passes
+/// the sample size.
+///
+/// `--summary-only` plays the game but writes the number of points and the `--stats` report to
+/// stdout (or `--output`) instead of the points themselves, for using the tool as an analyzer.
+///
+/// `--rotate-output DEG` turns the whole cloud counterclockwise by DEG degrees (not radians)
+/// around the centroid of the shape it's built on, e.g. `--rotate-output 180` to stand the
+/// Sierpinski triangle on its point. Points are rotated as they're written so nothing is held
+/// back.
+///
+/// `--invert-y` flips the fractal upside down within the box around the shape it's built on, for
+/// screen and image coordinates where y grows downward. It applies to `--gif` and `--svg` images
+/// too, which are otherwise drawn with y growing upward.
+///
+/// `--mirror x` writes every point twice, once as generated and once reflected across the line
+/// through the centroid of the shape it's built on running parallel to the x axis, i.e. flipped
+/// top to bottom. `--mirror y` reflects across the parallel to the y axis, flipping left to right.
+/// A group column of 0 for the originals and 1 for the reflections is added, before any `--tile`
+/// group. Both copies then go through `--rotate-output` and `--invert-y`.
+///
+/// `--affine A,B,C,D,E,F` maps every point `(x, y)` to `(A x + B y + E, C x + D y + F)` on its
+/// way out, which covers any rotation, shear, scaling, reflection, and translation in one flag,
+/// e.g. `--affine 1,0.5,0,1,0,0` to shear right. It takes exactly six numbers.
+///
+/// `--rotate-output`, `--invert-y`, and `--affine` are applied in that order whatever order
+/// they're given in, the first two placed relative to the shape the fractal is built on. Points are
+/// clipped by `--clip-to-polygon` before they're transformed, and tiled, scaled, and rounded after.
+///
+/// `--c RE,IM` sets the constant of `julia`'s map `z -> z^2 + c`, -0.123,0.745 (the Douady
+/// rabbit) by default. `--c -0.8,0.156` and `--c 0.285,0.01` are other favorites.
+///
+/// `--a RX` and `--b RY` set the semi-axes along x and y of the ellipse `ellipse-attractor`
+/// jumps towards, 50 and 30 by default.
+///
+/// `--variant NAME` picks the species `barnsley-fern` draws: `barnsley` (the default),
+/// `thelypteridaceae`, or `leptosporangiate`, or their number 0 to 2.
+///
+/// `--config PATH` lists the transforms for `ifs`, one `transform = a, b, c, d, e, f, weight`
+/// line each, mapping `(x, y)` to `(a x + b y + e, c x + d y + f)`. Each iteration picks a
+/// transform with probability proportional to its weight. Lines starting with `#` are comments.
+/// Without it `ifs` draws the Barnsley fern.
+///
+/// `--svg-input PATH` reads the polygon `svg-polygon` is built on from an SVG file, e.g. one
+/// drawn in a vector editor. It takes the first `<polygon points="...">` or `<path d="...">` in
+/// the file. Only straight edges are supported: paths may use `M`, `L`, and `Z` (or their relative
+/// forms) for a single closed shape, and curves, arcs, `H`, `V`, and `transform`s are errors.
+/// The shape is flipped vertically since SVG's y axis points down. Without it `svg-polygon`
+/// plays on the triangle `sierpinski-triangle` uses.
+///
+/// `--contains X,Y` prints `yes` and exits with status 0 if the point (X, Y) is inside or on the
+/// edge of the polygon the fractal is built on, and prints `no` and exits with status 1 if it
+/// isn't, without generating anything. E.g. `chaos-game square-one --contains 50,50 && ...`. It's
+/// an error for the fractals that aren't built on a polygon and the other subcommands.
+///
+/// `--print-command` prints the command line that reproduces the run to stderr once the defaults
+/// are resolved, e.g. `chaos-game vicsek --iterations 1000000 --seed 8317094218543152241`. It
+/// always includes the seed, `--seed` or the one drawn from the OS, along with the iterations and
+/// anything taken from the environment, so it can be copied as is to get the same points again.
+///
+/// `--meta PATH` writes a JSON description of the run next to the points for the polygon
+/// fractals: the fractal's name, the vertices it's built on and the box around them, the jump
+/// distance, the seed (`--seed` or the logged one), the iterations, `--target-set` (`null`
+/// without one), and the rule for picking targets as a `--restrict-expr` expression, either the
+/// flag's or the fractal's own like square-one's `c != p` (`null` for a uniform pick). The points
+/// themselves are written as usual, so the point file stays plain data.
+///
+/// `--verify` checks that none of the points landed in the regions the fractal should leave
+/// empty, like the upside down triangle in the middle of `sierpinski-triangle` or the middle of
+/// each side of `vicsek`, as a check on the algorithm. It reports on stderr and exits with status
+/// 1 if any did. The first 50 points are let off while the game settles, and points within 1% of
+/// a hole's edge don't count. Fractals that don't declare any holes pass trivially.
+///
+/// `--cell-count-check` checks the fractal is made of copies of itself the way it should be. A
+/// grid with cells the size of its copies `--depth` levels down is laid over the shape it's built
+/// on, e.g. 16 x 16 for `sierpinski-triangle` at the default depth of 4, and the number of cells
+/// the points land in is reported on stderr next to the number of copies in theory, 3^4 = 81, and
+/// their ratio. Copies that straddle cells push the ratio above 1 and missing copies pull it below.
+/// Only `sierpinski-triangle`, `vicsek`, and `hexaflake` declare their copies so far.
+///
+/// Ctrl-C stops the run early but still writes out the points generated so far, flushing the
+/// output and finishing `--hull`, `--sample`, images, and the rest as if the run had been that
+/// long, then exits with status 130. Press it twice to quit without waiting.
+///
+/// `--estimate-memory` prints roughly how many rows the run would write, how big they'd be as
+/// text, and how much memory would be held on to for modes that buffer points, then exits without
+/// generating anything. Sizes of `--gif` and `--svg` images aren't estimated.
+///
+/// `--non-finite skip|abort` decides what happens to a point with a NaN or infinite coordinate,
+/// e.g. from an `ifs` config that blows up. `skip`, the default, leaves it out and warns on stderr
+/// how many were left out. `abort` stops with an error at the first one.
+///
+/// `--convergence` prints `iteration distance` rows measuring how far each point lands from its
+/// nearest predecessor instead of the points themselves.
+///
+/// `--trajectory` marks the output as a path rather than a cloud. The points are the same
+/// successive jumps, but a gnuplot hint is written first so a short run can be viewed with
+/// `plot 'plots/trajectory.txt' with linespoints` to watch the game hop between vertices.
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by `--quiet`. Checked by `diagnostic!` before anything non-essential is written to stderr.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Set when the run is interrupted with Ctrl-C. Generation stops at the next point and whatever
+/// was generated is written out as if the run had ended there.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Like `eprintln!` but silenced by `--quiet`. Use it for progress, stats, and other chatter that
+/// isn't point data or a hard error.
+macro_rules! diagnostic {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Everything that can go wrong with the flags, files, and shapes a run is given. It's returned up
+/// to `main`, which reports it as `error: ...` and exits with status 1.
+#[derive(Debug)]
+enum ChaosError {
+    /// Reading or writing failed
+    Io(io::Error),
+    /// Text that doesn't parse, like a malformed point or flag value
+    Parse(String),
+    /// Input that parses but can't be used, like a polygon with two vertices or clashing flags
+    Invalid(String),
+    /// One of the others, in the file at `path`
+    InFile {
+        path: String,
+        error: Box<ChaosError>,
+    },
+    /// Writing the points out failed, most likely to a socket `--output` whose reader went away
+    Output(io::Error),
+    /// The point `--contains` asked about is outside the polygon. It isn't an error as such, "no"
+    /// has already been printed, but `main` exits with status 1 for it like `grep` finding nothing.
+    Outside,
+}
+
+impl ChaosError {
+    /// Attributes errors to the file at `path`, for `map_err`
+    fn in_file<E: Into<ChaosError>>(path: &str) -> impl FnOnce(E) -> ChaosError + '_ {
+        move |error| ChaosError::InFile {
+            path: path.to_string(),
+            error: Box::new(error.into()),
+        }
+    }
+}
+
+impl std::fmt::Display for ChaosError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChaosError::Io(err) => write!(f, "{}", err),
+            ChaosError::Parse(message) | ChaosError::Invalid(message) => write!(f, "{}", message),
+            ChaosError::InFile { path, error } => write!(f, "{}: {}", path, error),
+            ChaosError::Output(err) => write!(f, "couldn't write output: {}", err),
+            ChaosError::Outside => write!(f, "the point is outside the polygon"),
+        }
+    }
+}
+
+impl std::error::Error for ChaosError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChaosError::Io(err) | ChaosError::Output(err) => Some(err),
+            ChaosError::InFile { error, .. } => Some(error.as_ref()),
+            ChaosError::Parse(_) | ChaosError::Invalid(_) | ChaosError::Outside => None,
+        }
+    }
+}
+
+impl From<io::Error> for ChaosError {
+    fn from(err: io::Error) -> Self {
+        ChaosError::Io(err)
+    }
+}
+
+impl From<std::num::ParseFloatError> for ChaosError {
+    fn from(err: std::num::ParseFloatError) -> Self {
+        ChaosError::Parse(err.to_string())
+    }
+}
+
+impl From<std::num::ParseIntError> for ChaosError {
+    fn from(err: std::num::ParseIntError) -> Self {
+        ChaosError::Parse(err.to_string())
+    }
+}
+
+/// The contents of the file at `path`, with the path in any error
+fn read_file(path: &str) -> Result<String, ChaosError> {
+    std::fs::read_to_string(path).map_err(ChaosError::in_file(path))
+}
+
+/// A scratch file in the temp directory for `--threads` and `--panels` runs to write to, removed
+/// when dropped. The name ends in a random suffix and the file is created fresh, so another user
+/// can't guess it ahead of time and leave a file or link there for the run to write through.
+struct TempFile {
+    path: String,
+}
+
+impl TempFile {
+    fn new(purpose: &str) -> Result<Self, ChaosError> {
+        loop {
+            let name = format!(
+                "chaos-game-{}-{}-{:016x}",
+                std::process::id(),
+                purpose,
+                rand::random::<u64>()
+            );
+            let path = std::env::temp_dir()
+                .join(name)
+                .to_string_lossy()
+                .into_owned();
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(Self { path }),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(ChaosError::in_file(&path)(err)),
+            }
+        }
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 struct Point {
@@ -31,178 +596,7880 @@ impl Point {
         Self { x, y }
     }
 
-    fn jump_towards(&self, other: &Self, distance: f64) -> Self {
-        let x = (self.x * (1.0 - distance)) + (other.x * distance);
-        let y = (self.y * (1.0 - distance)) + (other.y * distance);
+    /// Linearly interpolates from `self` at `t = 0` to `other` at `t = 1`. Values outside 0..=1
+    /// extrapolate along the same line.
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        let x = (self.x * (1.0 - t)) + (other.x * t);
+        let y = (self.y * (1.0 - t)) + (other.y * t);
         Self { x, y }
     }
-}
 
-trait Polygon {
-    fn points(&self) -> Vec<Point>;
-
-    /// Assumes that adjacent points are next to each other in the array that comes out of points
-    fn medial_points(&self) -> Vec<Point> {
-        let points = self.points();
+    /// The chaos game's move: `distance` of the way from `self` to `other`, so 0 stays put, 0.5 is
+    /// the midpoint, and 1 lands on `other`. The same as `lerp`.
+    fn jump_towards(&self, other: &Self, distance: f64) -> Self {
+        self.lerp(other, distance)
+    }
 
-        let mut new_points = Vec::with_capacity(points.len());
-        for (i, point) in self.points().iter().enumerate() {
-            let next_point_index = (i + 1) % points.len();
-            let next_point = points[next_point_index];
+    /// Jumps towards `other` like `jump_towards` and then rotates the result counterclockwise
+    /// around `other` by `radians`. Enough to express the two-map dragon fractals without a
+    /// general affine transform.
+    fn jump_towards_rotated(&self, other: &Self, distance: f64, radians: f64) -> Self {
+        let jumped = self.jump_towards(other, distance);
+        let offset = Point::new(jumped.x - other.x, jumped.y - other.y).rotate(radians);
+        Self::new(other.x + offset.x, other.y + offset.y)
+    }
 
-            let medial_point = point.midpoint(&next_point);
-            new_points.push(medial_point);
-        }
+    /// The principal square root treating the point as the complex number `x + yi`, the one with
+    /// a non-negative real part. The other root is its negation.
+    fn complex_sqrt(&self) -> Self {
+        let modulus = self.x.hypot(self.y);
+        let re = ((modulus + self.x) / 2.0).sqrt();
+        let im = ((modulus - self.x) / 2.0).sqrt();
+        Self::new(re, if self.y < 0.0 { -im } else { im })
+    }
 
-        new_points
+    /// The point `r` from the origin at `theta` radians counterclockwise from the x axis
+    fn from_polar(r: f64, theta: f64) -> Self {
+        let (sin, cos) = theta.sin_cos();
+        Self::new(r * cos, r * sin)
     }
 
-    /// Prints the x y coordinates of the generated fractal to stdout separated by a space. This can be fed into
-    /// gnuplot to see the resulting fractal. `jump_distance` is how far to jump towards the next
-    /// vertex and `next_point` is a closure to determine the vertex.
-    fn chaos_game<F>(&self, iterations: usize, jump_distance: f64, next_point: &mut F)
-    where
-        F: FnMut() -> Point,
-    {
-        let mut rng = rand::thread_rng();
-        let mut current_point = *self
-            .medial_points()
-            .choose(&mut rng)
-            .expect("Shouldn't be empty");
+    /// Rotates counterclockwise around the origin
+    fn rotate(&self, radians: f64) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        let x = self.x * cos - self.y * sin;
+        let y = self.x * sin + self.y * cos;
+        Self { x, y }
+    }
 
-        for _ in 0..iterations {
-            println!("{} {}", current_point.x, current_point.y);
-            let reference_point = next_point();
-            let new_point = current_point.jump_towards(&reference_point, jump_distance);
-            current_point = new_point;
+    /// The average of `points`, or the origin if there aren't any
+    fn centroid(points: &[Point]) -> Self {
+        if points.is_empty() {
+            return Self::new(0.0, 0.0);
         }
+        let count = points.len() as f64;
+        let x = points.iter().map(|point| point.x).sum::<f64>() / count;
+        let y = points.iter().map(|point| point.y).sum::<f64>() / count;
+        Self::new(x, y)
     }
-}
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-struct Triangle {
-    pub a: Point,
-    pub b: Point,
-    pub c: Point,
-}
+    fn translate(&self, dx: f64, dy: f64) -> Self {
+        Self::new(self.x + dx, self.y + dy)
+    }
 
-impl Triangle {
-    pub fn new(a: Point, b: Point, c: Point) -> Self {
-        Self { a, b, c }
+    fn distance(&self, other: &Self) -> f64 {
+        self.distance_squared(other).sqrt()
     }
 
-    fn new_equilateral(length: f64) -> Self {
-        let a = Point::new(0.0, 0.0);
-        let b = Point::new(length, 0.0);
-        let c = Point::new(length / 2.0, length);
-        Triangle::new(a, b, c)
+    /// Cheaper than `distance` when only comparing distances
+    fn distance_squared(&self, other: &Self) -> f64 {
+        let dx = self.x - other.x;
+        let dy = self.y - other.y;
+        dx * dx + dy * dy
     }
 }
 
-impl Polygon for Triangle {
-    fn points(&self) -> Vec<Point> {
-        vec![self.a, self.b, self.c]
+/// Exact arithmetic for `--exact`
+#[cfg(feature = "exact")]
+mod exact {
+    use super::Point;
+
+    /// A fraction in lowest terms with a positive denominator. Operations return `None` instead of
+    /// overflowing.
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    pub struct Ratio {
+        numer: i128,
+        denom: i128,
     }
-}
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-struct Quadrilateral {
-    pub a: Point,
-    pub b: Point,
-    pub c: Point,
-    pub d: Point,
-}
+    impl Ratio {
+        fn new(numer: i128, denom: i128) -> Self {
+            let divisor = gcd(numer, denom).max(1);
+            let sign = if denom < 0 { -1 } else { 1 };
+            Self {
+                numer: sign * numer / divisor,
+                denom: sign * denom / divisor,
+            }
+        }
 
-impl Quadrilateral {
-    pub fn new(a: Point, b: Point, c: Point, d: Point) -> Self {
-        Self { a, b, c, d }
+        /// Every finite float is a fraction with a power of two denominator. Panics on floats too
+        /// large or small to hold that way.
+        pub fn from_f64(value: f64) -> Self {
+            assert!(value.is_finite(), "{} can't be made exact", value);
+            if value == 0.0 {
+                return Self::new(0, 1);
+            }
+            let bits = value.to_bits();
+            let sign = if bits >> 63 == 0 { 1 } else { -1 };
+            let exponent = ((bits >> 52) & 0x7ff) as i32;
+            let fraction = (bits & 0xf_ffff_ffff_ffff) as i128;
+            // value = sign * mantissa * 2^power
+            let (mantissa, power) = if exponent == 0 {
+                (fraction, -1074)
+            } else {
+                (fraction | 1 << 52, exponent - 1075)
+            };
+            if power >= 0 {
+                let numer = mantissa
+                    .checked_shl(power as u32)
+                    .filter(|shifted| shifted >> power == mantissa)
+                    .unwrap_or_else(|| panic!("{} is too large to make exact", value));
+                Self::new(sign * numer, 1)
+            } else {
+                assert!(power > -127, "{} is too small to make exact", value);
+                Self::new(sign * mantissa, 1 << -power)
+            }
+        }
+
+        pub fn to_f64(self) -> f64 {
+            self.numer as f64 / self.denom as f64
+        }
+
+        /// Halfway between `self` and `other`
+        pub fn midpoint(self, other: Self) -> Option<Self> {
+            let divisor = gcd(self.denom, other.denom);
+            let left = self.numer.checked_mul(other.denom / divisor)?;
+            let right = other.numer.checked_mul(self.denom / divisor)?;
+            let denom = (self.denom / divisor)
+                .checked_mul(other.denom)?
+                .checked_mul(2)?;
+            Some(Self::new(left.checked_add(right)?, denom))
+        }
     }
 
-    fn square(length: f64) -> Self {
-        let a = Point::new(0.0, 0.0);
-        let b = Point::new(length, 0.0);
-        let c = Point::new(length, length);
-        let d = Point::new(0.0, length);
-        Self::new(a, b, c, d)
+    fn gcd(a: i128, b: i128) -> i128 {
+        let (mut a, mut b) = (a.abs(), b.abs());
+        while b != 0 {
+            let remainder = a % b;
+            a = b;
+            b = remainder;
+        }
+        a
     }
-}
 
-impl Polygon for Quadrilateral {
-    fn points(&self) -> Vec<Point> {
-        vec![self.a, self.b, self.c, self.d]
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    pub struct ExactPoint {
+        pub x: Ratio,
+        pub y: Ratio,
     }
-}
 
-const ITERATIONS: usize = 1000000;
+    impl ExactPoint {
+        pub fn from_point(point: Point) -> Self {
+            Self {
+                x: Ratio::from_f64(point.x),
+                y: Ratio::from_f64(point.y),
+            }
+        }
 
-fn main() {
-    match std::env::args().nth(1) {
-        Some(cmd) if cmd == "sierpinski-triangle" => sierpinski_triangle(ITERATIONS),
-        Some(cmd) if cmd == "square-one" => square_one(ITERATIONS),
-        Some(cmd) if cmd == "square-two" => square_two(ITERATIONS),
-        Some(cmd) if cmd == "vicsek" => vicsek_fractal(ITERATIONS),
-        None => sierpinski_triangle(ITERATIONS),
-        Some(unrecognized) => panic!("{} is not yet implemented", unrecognized),
+        pub fn to_point(self) -> Point {
+            Point::new(self.x.to_f64(), self.y.to_f64())
+        }
+
+        pub fn midpoint(&self, other: &Self) -> Option<Self> {
+            Some(Self {
+                x: self.x.midpoint(other.x)?,
+                y: self.y.midpoint(other.y)?,
+            })
+        }
     }
-}
 
-#[allow(unused)]
-fn sierpinski_triangle(iterations: usize) {
-    let jump_distance = 0.5;
-    let triangle = Triangle::new_equilateral(100.0);
-    let mut rng = rand::thread_rng();
-    let points = triangle.points();
-    triangle.chaos_game(iterations, jump_distance, &mut || {
-        *points.choose(&mut rng).expect("Shouldn't be empty")
-    });
-}
+    #[cfg(test)]
+    mod tests {
+        use super::*;
 
-#[allow(unused)]
-fn square_one(iterations: usize) {
-    let jump_distance = 0.5;
-    let square = Quadrilateral::square(100.0);
-    let points = square.points();
+        #[test]
+        fn from_f64_round_trips() {
+            for value in &[0.0, 1.0, -2.5, 0.1, 100.0, 33.333333333333336, -0.000_001] {
+                assert_eq!(Ratio::from_f64(*value).to_f64(), *value);
+            }
+        }
 
-    let mut rng = rand::thread_rng();
-    let mut prev_vertex = points.choose(&mut rng).expect("Shouldn't be empty");
-    square.chaos_game(iterations, jump_distance, &mut || loop {
-        let new_vertex = points.choose(&mut rng).expect("Shouldn't be empty");
-        if (new_vertex.x != prev_vertex.x || new_vertex.y != prev_vertex.y) {
-            prev_vertex = new_vertex;
-            break *new_vertex;
+        #[test]
+        fn midpoint_is_exact() {
+            let third = Ratio::new(1, 3);
+            let half = Ratio::from_f64(0.5);
+            assert_eq!(third.midpoint(half), Some(Ratio::new(5, 12)));
         }
-    });
+
+        #[test]
+        fn repeated_halving_overflows_instead_of_rounding() {
+            let zero = Ratio::from_f64(0.0);
+            let mut value = Ratio::from_f64(1.0);
+            let mut halvings = 0;
+            while let Some(half) = value.midpoint(zero) {
+                value = half;
+                halvings += 1;
+            }
+            assert_eq!(value, Ratio::new(1, 1 << halvings));
+            assert!(halvings > 120);
+        }
+    }
 }
 
-#[allow(unused)]
-fn square_two(iterations: usize) {
-    let jump_distance = 0.5;
-    let square = Quadrilateral::square(100.0);
-    let points = square.points();
+/// Animated GIF output for `--gif`, with a small encoder so no image crates are needed
+#[cfg(feature = "gif")]
+mod gif {
+    use super::{Bounds, Point};
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::io::{self, BufWriter, Write};
 
-    let mut rng = rand::thread_rng();
-    let mut prev_vertex = points.choose(&mut rng).expect("Shouldn't be empty");
-    square.chaos_game(iterations, jump_distance, &mut || loop {
-        let new_vertex = points.choose(&mut rng).expect("Shouldn't be empty");
-        if (new_vertex.x == prev_vertex.x || new_vertex.y == prev_vertex.y) {
-            prev_vertex = new_vertex;
-            break *new_vertex;
+    /// Width and height of every frame in pixels
+    const SIZE: usize = 256;
+    /// Hundredths of a second each frame is shown for
+    const DELAY: u16 = 10;
+    /// Background then point color, padded to the 4 entries the smallest LZW code size needs
+    const PALETTE: [u8; 12] = [255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    /// Rasterizes points into a growing image and writes a frame of it every `every` points, so
+    /// the animation shows the cloud filling in. Memory is one frame no matter how many points.
+    pub struct Animation {
+        file: BufWriter<File>,
+        bounds: Bounds,
+        pixels: Vec<u8>,
+        every: usize,
+        seen: usize,
+    }
+
+    impl Animation {
+        /// Points are placed relative to `bounds`, anything outside is clamped to the edge
+        pub fn create(path: &str, bounds: Bounds, every: usize) -> io::Result<Self> {
+            let mut file = BufWriter::new(File::create(path)?);
+            file.write_all(b"GIF89a")?;
+            file.write_all(&(SIZE as u16).to_le_bytes())?;
+            file.write_all(&(SIZE as u16).to_le_bytes())?;
+            // Global color table of 2^(1 + 1) entries, no background or aspect ratio
+            file.write_all(&[0b1000_0001, 0, 0])?;
+            file.write_all(&PALETTE)?;
+            // Loop forever
+            file.write_all(&[0x21, 0xff, 0x0b])?;
+            file.write_all(b"NETSCAPE2.0")?;
+            file.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+            Ok(Self {
+                file,
+                bounds,
+                pixels: vec![0; SIZE * SIZE],
+                every: every.max(1),
+                seen: 0,
+            })
         }
-    });
-}
 
-#[allow(unused)]
-fn vicsek_fractal(iterations: usize) {
-    let jump_distance = 0.66666666667;
-    let square = Quadrilateral::square(100.0);
-    let mut points = square.points();
-    let midpoint = points[0].midpoint(&points[2]);
-    points.push(midpoint);
+        pub fn add(&mut self, point: &Point) -> io::Result<()> {
+            let (column, row) = self.bounds.cell(point, SIZE, SIZE);
+            // Images count rows down from the top
+            self.pixels[(SIZE - 1 - row) * SIZE + column] = 1;
+            self.seen += 1;
+            if self.seen.is_multiple_of(self.every) {
+                self.write_frame()?;
+            }
+            Ok(())
+        }
 
-    let mut rng = rand::thread_rng();
-    let mut prev_vertex = points.choose(&mut rng).expect("Shouldn't be empty");
-    square.chaos_game(iterations, jump_distance, &mut || loop {
-        let new_vertex = points.choose(&mut rng).expect("Shouldn't be empty");
-        break *new_vertex;
-    });
+        /// Writes the last partial frame, if any, and ends the file
+        pub fn finish(mut self) -> io::Result<()> {
+            if !self.seen.is_multiple_of(self.every) {
+                self.write_frame()?;
+            }
+            self.file.write_all(&[0x3b])?;
+            self.file.flush()
+        }
+
+        fn write_frame(&mut self) -> io::Result<()> {
+            let delay = DELAY.to_le_bytes();
+            self.file
+                .write_all(&[0x21, 0xf9, 0x04, 0x00, delay[0], delay[1], 0x00, 0x00])?;
+            self.file.write_all(&[0x2c, 0, 0, 0, 0])?;
+            self.file.write_all(&(SIZE as u16).to_le_bytes())?;
+            self.file.write_all(&(SIZE as u16).to_le_bytes())?;
+            self.file.write_all(&[0x00, MIN_CODE_SIZE])?;
+            for block in lzw_encode(&self.pixels).chunks(255) {
+                self.file.write_all(&[block.len() as u8])?;
+                self.file.write_all(block)?;
+            }
+            self.file.write_all(&[0x00])
+        }
+    }
+
+    const MIN_CODE_SIZE: u8 = 2;
+    const MAX_CODES: u16 = 4096;
+
+    /// GIF flavored LZW: variable width codes packed least significant bit first, starting one
+    /// bit wider than `MIN_CODE_SIZE` and clearing the table once it's full
+    fn lzw_encode(pixels: &[u8]) -> Vec<u8> {
+        let clear = 1u16 << MIN_CODE_SIZE;
+        let end = clear + 1;
+        let mut bits = BitWriter::default();
+        let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+        let mut next_code = end + 1;
+        let mut code_size = MIN_CODE_SIZE + 1;
+
+        bits.write(clear, code_size);
+        let mut prefix = match pixels.first() {
+            Some(pixel) => u16::from(*pixel),
+            None => {
+                bits.write(end, code_size);
+                return bits.finish();
+            }
+        };
+        for &pixel in &pixels[1..] {
+            if let Some(&code) = table.get(&(prefix, pixel)) {
+                prefix = code;
+                continue;
+            }
+            bits.write(prefix, code_size);
+            if next_code < MAX_CODES {
+                if next_code == 1 << code_size {
+                    code_size += 1;
+                }
+                table.insert((prefix, pixel), next_code);
+                next_code += 1;
+            } else {
+                bits.write(clear, code_size);
+                table.clear();
+                next_code = end + 1;
+                code_size = MIN_CODE_SIZE + 1;
+            }
+            prefix = u16::from(pixel);
+        }
+        bits.write(prefix, code_size);
+        bits.write(end, code_size);
+        bits.finish()
+    }
+
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        buffer: u32,
+        length: u8,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, code: u16, size: u8) {
+            self.buffer |= u32::from(code) << self.length;
+            self.length += size;
+            while self.length >= 8 {
+                self.bytes.push(self.buffer as u8);
+                self.buffer >>= 8;
+                self.length -= 8;
+            }
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            if self.length > 0 {
+                self.bytes.push(self.buffer as u8);
+            }
+            self.bytes
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// A plain GIF LZW decoder to check the encoder against
+        fn lzw_decode(data: &[u8]) -> Vec<u8> {
+            let clear = 1usize << MIN_CODE_SIZE;
+            let end = clear + 1;
+            let initial: Vec<Vec<u8>> = (0..clear).map(|i| vec![i as u8]).collect();
+            let mut table = initial.clone();
+            let mut code_size = MIN_CODE_SIZE as u32 + 1;
+            let (mut buffer, mut length, mut position) = (0u32, 0u32, 0);
+            let mut previous: Option<Vec<u8>> = None;
+            let mut output = Vec::new();
+            loop {
+                while length < code_size {
+                    buffer |= u32::from(data[position]) << length;
+                    position += 1;
+                    length += 8;
+                }
+                let code = (buffer & ((1 << code_size) - 1)) as usize;
+                buffer >>= code_size;
+                length -= code_size;
+
+                if code == clear {
+                    table = initial.clone();
+                    table.push(Vec::new());
+                    table.push(Vec::new());
+                    code_size = MIN_CODE_SIZE as u32 + 1;
+                    previous = None;
+                    continue;
+                }
+                if code == end {
+                    return output;
+                }
+                let entry = match (&previous, table.get(code)) {
+                    (_, Some(entry)) => entry.clone(),
+                    (Some(previous), None) => {
+                        let mut entry = previous.clone();
+                        entry.push(previous[0]);
+                        entry
+                    }
+                    (None, None) => panic!("bad code {}", code),
+                };
+                output.extend(&entry);
+                if let Some(mut previous) = previous.take() {
+                    previous.push(entry[0]);
+                    table.push(previous);
+                    if table.len() == 1 << code_size && code_size < 12 {
+                        code_size += 1;
+                    }
+                }
+                previous = Some(entry);
+            }
+        }
+
+        #[test]
+        fn lzw_round_trips() {
+            let mut pixels = vec![0; SIZE * SIZE];
+            // A mix of long runs and noise to fill and reset the code table
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                *pixel = ((i * 7919) % 13 == 0 || i % 1024 < 3) as u8;
+            }
+            assert_eq!(lzw_decode(&lzw_encode(&pixels)), pixels);
+            assert_eq!(lzw_decode(&lzw_encode(&[1, 0, 1, 1])), vec![1, 0, 1, 1]);
+        }
+    }
+}
+
+/// Fractal curves made by repeatedly replacing every edge of a polyline with a scaled copy of a
+/// fixed shape, like the [Koch snowflake](https://en.wikipedia.org/wiki/Koch_snowflake). Unlike
+/// the chaos game this is deterministic and draws the curve itself rather than a cloud.
+mod subdivision {
+    use super::Point;
+
+    /// The shape an edge is replaced with, as the points between its ends. They're written for
+    /// the edge from (0, 0) to (1, 0) with positive y to the right of the edge, which is outward
+    /// for a counterclockwise polygon.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Rule {
+        pub points: Vec<Point>,
+    }
+
+    impl Rule {
+        /// Bumps the middle third out into an equilateral triangle
+        pub fn koch() -> Self {
+            Self::new(&[
+                (1.0 / 3.0, 0.0),
+                (0.5, 3.0_f64.sqrt() / 6.0),
+                (2.0 / 3.0, 0.0),
+            ])
+        }
+
+        /// The Koch rule with an 85 degree apex instead of 60. Each of the four pieces has
+        /// length `r` and the two middle ones meet at the apex, so `1/2 - r = r sin(42.5°)`.
+        pub fn cesaro() -> Self {
+            let half_apex = 42.5_f64.to_radians();
+            let r = 1.0 / (2.0 * (1.0 + half_apex.sin()));
+            Self::new(&[(r, 0.0), (0.5, r * half_apex.cos()), (1.0 - r, 0.0)])
+        }
+
+        /// Eight quarter length pieces stepping out and back in, the Minkowski sausage
+        pub fn minkowski() -> Self {
+            Self::new(&[
+                (0.25, 0.0),
+                (0.25, 0.25),
+                (0.5, 0.25),
+                (0.5, 0.0),
+                (0.5, -0.25),
+                (0.75, -0.25),
+                (0.75, 0.0),
+            ])
+        }
+
+        fn new(points: &[(f64, f64)]) -> Self {
+            Self {
+                points: points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+            }
+        }
+
+        /// The points replacing the edge from `start` to `end`, not including either end
+        fn apply(&self, start: &Point, end: &Point) -> impl Iterator<Item = Point> + '_ {
+            let (dx, dy) = (end.x - start.x, end.y - start.y);
+            let start = *start;
+            // Along the edge and a quarter turn clockwise from it
+            self.points.iter().map(move |point| {
+                Point::new(
+                    start.x + point.x * dx + point.y * dy,
+                    start.y + point.x * dy - point.y * dx,
+                )
+            })
+        }
+    }
+
+    impl std::str::FromStr for Rule {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "koch" => Ok(Rule::koch()),
+                "cesaro" => Ok(Rule::cesaro()),
+                "minkowski" => Ok(Rule::minkowski()),
+                other => Err(format!("{} is not a subdivision rule", other)),
+            }
+        }
+    }
+
+    /// Applies `rule` to every edge of `points` `depth` times. A closed polyline also has an edge
+    /// from the last point back to the first, and comes back with the first point repeated at
+    /// the end so it can be drawn `with lines`.
+    pub fn subdivide(points: &[Point], closed: bool, rule: &Rule, depth: usize) -> Vec<Point> {
+        let mut curve = points.to_vec();
+        if closed {
+            curve.extend(points.first());
+        }
+        for _ in 0..depth {
+            let mut next = Vec::with_capacity(curve.len() * (rule.points.len() + 1));
+            for edge in curve.windows(2) {
+                next.push(edge[0]);
+                next.extend(rule.apply(&edge[0], &edge[1]));
+            }
+            next.extend(curve.last());
+            curve = next;
+        }
+        curve
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn koch_replaces_each_edge_with_four() {
+            let line = [Point::new(0.0, 0.0), Point::new(3.0, 0.0)];
+            let curve = subdivide(&line, false, &Rule::koch(), 1);
+            assert_eq!(curve.len(), 5);
+            assert_eq!(curve[1], Point::new(1.0, 0.0));
+            // To the right of a left to right edge is down
+            assert!((curve[2].x - 1.5).abs() < 1e-12);
+            assert!((curve[2].y + 3.0_f64.sqrt() / 2.0).abs() < 1e-12);
+            assert_eq!(
+                subdivide(&line, false, &Rule::koch(), 3).len(),
+                4 * 4 * 4 + 1
+            );
+        }
+
+        #[test]
+        fn closed_curves_end_where_they_start() {
+            let triangle = [
+                Point::new(0.0, 0.0),
+                Point::new(1.0, 0.0),
+                Point::new(0.5, 1.0),
+            ];
+            let curve = subdivide(&triangle, true, &Rule::minkowski(), 2);
+            assert_eq!(curve.len(), 3 * 8 * 8 + 1);
+            assert_eq!(curve.first(), curve.last());
+            assert_eq!(subdivide(&triangle, true, &Rule::koch(), 0).len(), 4);
+        }
+
+        #[test]
+        fn cesaro_pieces_are_equal() {
+            let rule = Rule::cesaro();
+            let mut points = vec![Point::new(0.0, 0.0)];
+            points.extend(rule.points.iter().copied());
+            points.push(Point::new(1.0, 0.0));
+            let lengths: Vec<f64> = points.windows(2).map(|p| p[0].distance(&p[1])).collect();
+            for length in &lengths {
+                assert!((length - lengths[0]).abs() < 1e-12, "{:?}", lengths);
+            }
+        }
+    }
+}
+
+/// Rules for which target the chaos game may jump towards next, written as small integer
+/// expressions over the previous target `p`, the candidate `c`, and the number of targets `n`,
+/// e.g. `abs(c - p) != 2`. A candidate is allowed when the expression isn't 0.
+mod restriction {
+    /// A parsed expression
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Expr {
+        Number(i64),
+        Previous,
+        Candidate,
+        Targets,
+        Abs(Box<Expr>),
+        Negate(Box<Expr>),
+        Not(Box<Expr>),
+        Binary(Box<Expr>, Op, Box<Expr>),
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    pub enum Op {
+        Add,
+        Subtract,
+        Multiply,
+        Divide,
+        Remainder,
+        Equal,
+        NotEqual,
+        Less,
+        LessOrEqual,
+        Greater,
+        GreaterOrEqual,
+        And,
+        Or,
+    }
+
+    impl Expr {
+        /// Comparisons and logic give 1 for true and 0 for false. Dividing by zero gives 0, and
+        /// remainders are never negative so `(c - p) % n` counts steps around the polygon.
+        pub fn evaluate(&self, previous: i64, candidate: i64, targets: i64) -> i64 {
+            let eval = |expr: &Expr| expr.evaluate(previous, candidate, targets);
+            match self {
+                Expr::Number(value) => *value,
+                Expr::Previous => previous,
+                Expr::Candidate => candidate,
+                Expr::Targets => targets,
+                Expr::Abs(expr) => eval(expr).wrapping_abs(),
+                Expr::Negate(expr) => eval(expr).wrapping_neg(),
+                Expr::Not(expr) => i64::from(eval(expr) == 0),
+                Expr::Binary(left, op, right) => {
+                    let (left, right) = (eval(left), eval(right));
+                    match op {
+                        Op::Add => left.wrapping_add(right),
+                        Op::Subtract => left.wrapping_sub(right),
+                        Op::Multiply => left.wrapping_mul(right),
+                        Op::Divide => left.checked_div(right).unwrap_or(0),
+                        Op::Remainder => left.checked_rem_euclid(right).unwrap_or(0),
+                        Op::Equal => i64::from(left == right),
+                        Op::NotEqual => i64::from(left != right),
+                        Op::Less => i64::from(left < right),
+                        Op::LessOrEqual => i64::from(left <= right),
+                        Op::Greater => i64::from(left > right),
+                        Op::GreaterOrEqual => i64::from(left >= right),
+                        Op::And => i64::from(left != 0 && right != 0),
+                        Op::Or => i64::from(left != 0 || right != 0),
+                    }
+                }
+            }
+        }
+
+        /// Whether the game may jump towards target `candidate` after target `previous`
+        pub fn allows(&self, previous: usize, candidate: usize, targets: usize) -> bool {
+            self.evaluate(previous as i64, candidate as i64, targets as i64) != 0
+        }
+    }
+
+    impl Op {
+        fn symbol(self) -> &'static str {
+            match self {
+                Op::Add => "+",
+                Op::Subtract => "-",
+                Op::Multiply => "*",
+                Op::Divide => "/",
+                Op::Remainder => "%",
+                Op::Equal => "==",
+                Op::NotEqual => "!=",
+                Op::Less => "<",
+                Op::LessOrEqual => "<=",
+                Op::Greater => ">",
+                Op::GreaterOrEqual => ">=",
+                Op::And => "&&",
+                Op::Or => "||",
+            }
+        }
+    }
+
+    /// Writes the expression back out in the syntax it's parsed from, with every operation inside
+    /// another in parentheses, e.g. `((c - p) % n) != 2`
+    impl std::fmt::Display for Expr {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let operand = |expr: &Expr| match expr {
+                Expr::Binary(..) => format!("({})", expr),
+                expr => expr.to_string(),
+            };
+            match self {
+                Expr::Number(value) => write!(f, "{}", value),
+                Expr::Previous => write!(f, "p"),
+                Expr::Candidate => write!(f, "c"),
+                Expr::Targets => write!(f, "n"),
+                Expr::Abs(expr) => write!(f, "abs({})", expr),
+                Expr::Negate(expr) => write!(f, "-{}", operand(expr)),
+                Expr::Not(expr) => write!(f, "!{}", operand(expr)),
+                Expr::Binary(left, op, right) => {
+                    write!(f, "{} {} {}", operand(left), op.symbol(), operand(right))
+                }
+            }
+        }
+    }
+
+    impl std::str::FromStr for Expr {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut parser = Parser {
+                tokens: tokenize(s)?,
+                position: 0,
+            };
+            let expr = parser.or()?;
+            match parser.tokens.get(parser.position) {
+                None => Ok(expr),
+                Some(token) => Err(format!("unexpected {:?} in {}", token, s)),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        Number(i64),
+        Name(String),
+        Symbol(&'static str),
+    }
+
+    /// Longest first, so `<=` isn't read as `<` then `=`
+    const SYMBOLS: [&str; 16] = [
+        "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")",
+    ];
+
+    fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+        let mut tokens = Vec::new();
+        let mut rest = s.trim_start();
+        while let Some(next) = rest.chars().next() {
+            if next.is_ascii_digit() {
+                let end = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let number = rest[..end]
+                    .parse()
+                    .map_err(|_| format!("{} is too big", &rest[..end]))?;
+                tokens.push(Token::Number(number));
+                rest = &rest[end..];
+            } else if next.is_ascii_alphabetic() {
+                let end = rest
+                    .find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(rest.len());
+                tokens.push(Token::Name(rest[..end].to_string()));
+                rest = &rest[end..];
+            } else {
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|symbol| rest.starts_with(*symbol))
+                    .ok_or_else(|| format!("unexpected {:?} in {}", next, s))?;
+                tokens.push(Token::Symbol(symbol));
+                rest = &rest[symbol.len()..];
+            }
+            rest = rest.trim_start();
+        }
+        Ok(tokens)
+    }
+
+    /// Recursive descent from the loosest binding operator, `||`, down to single values
+    struct Parser {
+        tokens: Vec<Token>,
+        position: usize,
+    }
+
+    impl Parser {
+        fn eat(&mut self, symbol: &str) -> bool {
+            let matches = matches!(
+                self.tokens.get(self.position),
+                Some(Token::Symbol(found)) if *found == symbol
+            );
+            if matches {
+                self.position += 1;
+            }
+            matches
+        }
+
+        /// Parses `next (op next)*` for any of `ops`, grouping to the left
+        fn binary(
+            &mut self,
+            ops: &[(&str, Op)],
+            next: fn(&mut Self) -> Result<Expr, String>,
+        ) -> Result<Expr, String> {
+            let mut expr = next(self)?;
+            'outer: loop {
+                for (symbol, op) in ops {
+                    if self.eat(symbol) {
+                        expr = Expr::Binary(Box::new(expr), *op, Box::new(next(self)?));
+                        continue 'outer;
+                    }
+                }
+                return Ok(expr);
+            }
+        }
+
+        fn or(&mut self) -> Result<Expr, String> {
+            self.binary(&[("||", Op::Or)], Self::and)
+        }
+
+        fn and(&mut self) -> Result<Expr, String> {
+            self.binary(&[("&&", Op::And)], Self::comparison)
+        }
+
+        fn comparison(&mut self) -> Result<Expr, String> {
+            let ops = [
+                ("==", Op::Equal),
+                ("!=", Op::NotEqual),
+                ("<=", Op::LessOrEqual),
+                (">=", Op::GreaterOrEqual),
+                ("<", Op::Less),
+                (">", Op::Greater),
+            ];
+            self.binary(&ops, Self::sum)
+        }
+
+        fn sum(&mut self) -> Result<Expr, String> {
+            self.binary(&[("+", Op::Add), ("-", Op::Subtract)], Self::product)
+        }
+
+        fn product(&mut self) -> Result<Expr, String> {
+            let ops = [("*", Op::Multiply), ("/", Op::Divide), ("%", Op::Remainder)];
+            self.binary(&ops, Self::unary)
+        }
+
+        fn unary(&mut self) -> Result<Expr, String> {
+            if self.eat("-") {
+                Ok(Expr::Negate(Box::new(self.unary()?)))
+            } else if self.eat("!") {
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            } else {
+                self.value()
+            }
+        }
+
+        fn value(&mut self) -> Result<Expr, String> {
+            let token = self.tokens.get(self.position).cloned();
+            self.position += 1;
+            match token {
+                Some(Token::Number(value)) => Ok(Expr::Number(value)),
+                Some(Token::Name(name)) => match name.as_str() {
+                    "p" => Ok(Expr::Previous),
+                    "c" => Ok(Expr::Candidate),
+                    "n" => Ok(Expr::Targets),
+                    "abs" => {
+                        let inner = self.parenthesized()?;
+                        Ok(Expr::Abs(Box::new(inner)))
+                    }
+                    other => Err(format!("{} isn't p, c, n, or abs", other)),
+                },
+                Some(Token::Symbol("(")) => {
+                    self.position -= 1;
+                    self.parenthesized()
+                }
+                Some(token) => Err(format!("unexpected {:?}", token)),
+                None => Err("the expression ends too soon".to_string()),
+            }
+        }
+
+        fn parenthesized(&mut self) -> Result<Expr, String> {
+            if !self.eat("(") {
+                return Err("expected (".to_string());
+            }
+            let expr = self.or()?;
+            if !self.eat(")") {
+                return Err("expected )".to_string());
+            }
+            Ok(expr)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn eval(s: &str, previous: i64, candidate: i64) -> i64 {
+            s.parse::<Expr>().unwrap().evaluate(previous, candidate, 4)
+        }
+
+        #[test]
+        fn operators_bind_like_c() {
+            assert_eq!(eval("1 + 2 * 3", 0, 0), 7);
+            assert_eq!(eval("(1 + 2) * 3", 0, 0), 9);
+            assert_eq!(eval("10 - 3 - 2", 0, 0), 5);
+            assert_eq!(eval("-3 % n", 0, 0), 1);
+            assert_eq!(eval("1 / 0", 0, 0), 0);
+            assert_eq!(eval("1 < 2 && 2 < 1 || !0", 0, 0), 1);
+        }
+
+        #[test]
+        fn variables_are_previous_candidate_and_targets() {
+            let expr: Expr = "abs(c - p) != 2".parse().unwrap();
+            assert!(expr.allows(0, 1, 4));
+            assert!(!expr.allows(0, 2, 4));
+            assert!(!expr.allows(3, 1, 4));
+            assert_eq!(eval("(c - p) % n", 3, 0), 1);
+        }
+
+        #[test]
+        fn expressions_print_as_they_parse() {
+            for (text, printed) in &[
+                ("c != p", "c != p"),
+                ("(c - p) % n != 2", "((c - p) % n) != 2"),
+                (
+                    "!(abs(c - p) == 1) || -c < 0",
+                    "!(abs(c - p) == 1) || (-c < 0)",
+                ),
+            ] {
+                let expr: Expr = text.parse().unwrap();
+                assert_eq!(expr.to_string(), *printed);
+                assert_eq!(printed.parse::<Expr>(), Ok(expr));
+            }
+        }
+
+        #[test]
+        fn malformed_expressions_are_errors() {
+            for bad in &[
+                "", "c !=", "x == 1", "abs c", "(c", "c == 1)", "c $ 1", "c = 1",
+            ] {
+                assert!(bad.parse::<Expr>().is_err(), "{}", bad);
+            }
+        }
+    }
+}
+
+/// Reads the vertices of a shape drawn in a vector editor from an SVG file. Only straight edges
+/// are understood: the first `<polygon points="...">` or a `<path d="...">` made of moveto and
+/// lineto commands. Curves, arcs, and transforms aren't, and paths using them are errors.
+mod svg_input {
+    use super::Point;
+
+    /// The vertices of the first polygon or path in `text`, in order around its edge. SVG's y
+    /// axis points down, so y is negated to keep the shape the right way up when plotted.
+    pub fn parse(text: &str) -> Result<Vec<Point>, String> {
+        let polygon = element(text, "polygon");
+        let path = element(text, "path");
+        let mut points = match (polygon, path) {
+            (Some((start, tag)), path) if path.is_none_or(|(other, _)| start < other) => {
+                let points = attribute(tag, "points").ok_or("<polygon> has no points")?;
+                polygon_points(points)?
+            }
+            (_, Some((_, tag))) => {
+                let d = attribute(tag, "d").ok_or("<path> has no d")?;
+                path_points(d)?
+            }
+            _ => return Err("no <polygon> or <path> to read".to_string()),
+        };
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() < 3 {
+            return Err(format!(
+                "a polygon needs 3 vertices, found {}",
+                points.len()
+            ));
+        }
+        Ok(points.into_iter().map(|p| Point::new(p.x, -p.y)).collect())
+    }
+
+    /// The offset and text of the first `<name ...>` tag
+    fn element<'a>(text: &'a str, name: &str) -> Option<(usize, &'a str)> {
+        let open = format!("<{}", name);
+        let mut from = 0;
+        while let Some(index) = text[from..].find(&open) {
+            let start = from + index;
+            let rest = &text[start + open.len()..];
+            if rest.starts_with(|c: char| c.is_whitespace() || c == '/' || c == '>') {
+                let end = rest.find('>').unwrap_or(rest.len());
+                return Some((start, &rest[..end]));
+            }
+            from = start + open.len();
+        }
+        None
+    }
+
+    /// The value of the attribute `name` in the text of a tag, in single or double quotes
+    fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+        let mut from = 0;
+        while let Some(index) = tag[from..].find(name) {
+            let start = from + index;
+            from = start + name.len();
+            let preceded = tag[..start].ends_with(char::is_whitespace);
+            let rest = tag[from..].trim_start();
+            if !preceded || !rest.starts_with('=') {
+                continue;
+            }
+            let rest = rest[1..].trim_start();
+            let quote = rest.chars().next().filter(|&c| c == '"' || c == '\'')?;
+            let value = &rest[1..];
+            return value.find(quote).map(|end| &value[..end]);
+        }
+        None
+    }
+
+    fn polygon_points(points: &str) -> Result<Vec<Point>, String> {
+        let mut scanner = Scanner::new(points);
+        let mut vertices = Vec::new();
+        while let Some(x) = scanner.number()? {
+            let y = scanner.number()?.ok_or("points has an x without a y")?;
+            vertices.push(Point::new(x, y));
+        }
+        Ok(vertices)
+    }
+
+    fn path_points(d: &str) -> Result<Vec<Point>, String> {
+        let mut scanner = Scanner::new(d);
+        let mut vertices: Vec<Point> = Vec::new();
+        let mut command = None;
+        let mut closed = false;
+        loop {
+            match scanner.command() {
+                Some(next) => command = Some(next),
+                None if scanner.is_done() => break,
+                None => {}
+            }
+            let relative = command.is_some_and(|c: char| c.is_ascii_lowercase());
+            match command.map(|c| c.to_ascii_uppercase()) {
+                None if vertices.is_empty() => return Err("a path has to start with M".to_string()),
+                None => return Err("a path has numbers after its Z".to_string()),
+                Some('Z') => {
+                    closed = true;
+                    command = None;
+                }
+                Some(c @ 'M') | Some(c @ 'L') => {
+                    if closed || (c == 'M' && !vertices.is_empty()) {
+                        return Err("only a single closed shape is supported".to_string());
+                    }
+                    let x = scanner.number()?.ok_or("a path command is missing its x")?;
+                    let y = scanner.number()?.ok_or("a path command is missing its y")?;
+                    let point = match vertices.last() {
+                        Some(last) if relative => Point::new(last.x + x, last.y + y),
+                        _ => Point::new(x, y),
+                    };
+                    vertices.push(point);
+                    // Pairs after a moveto are implicit linetos
+                    command = Some(if relative { 'l' } else { 'L' });
+                }
+                Some(other) => {
+                    return Err(format!(
+                        "only M, L, and Z path commands are supported, found {}",
+                        other
+                    ))
+                }
+            }
+        }
+        Ok(vertices)
+    }
+
+    /// Splits SVG's number lists, where numbers are separated by whitespace, commas, or nothing
+    /// at all when the next one starts with a sign or a second decimal point, e.g. `10-5.5.5`.
+    struct Scanner<'a> {
+        rest: &'a str,
+    }
+
+    impl<'a> Scanner<'a> {
+        fn new(text: &'a str) -> Self {
+            Self { rest: text }
+        }
+
+        fn skip_separators(&mut self) {
+            self.rest = self
+                .rest
+                .trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        }
+
+        fn is_done(&mut self) -> bool {
+            self.skip_separators();
+            self.rest.is_empty()
+        }
+
+        fn command(&mut self) -> Option<char> {
+            self.skip_separators();
+            let c = self.rest.chars().next().filter(char::is_ascii_alphabetic)?;
+            self.rest = &self.rest[1..];
+            Some(c)
+        }
+
+        fn number(&mut self) -> Result<Option<f64>, String> {
+            self.skip_separators();
+            let bytes = self.rest.as_bytes();
+            let mut end = 0;
+            if end < bytes.len() && (bytes[end] == b'-' || bytes[end] == b'+') {
+                end += 1;
+            }
+            let digits = |mut i: usize| {
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+                i
+            };
+            end = digits(end);
+            if end < bytes.len() && bytes[end] == b'.' {
+                end = digits(end + 1);
+            }
+            if end < bytes.len() && (bytes[end] == b'e' || bytes[end] == b'E') {
+                let mut exponent = end + 1;
+                if exponent < bytes.len() && (bytes[exponent] == b'-' || bytes[exponent] == b'+') {
+                    exponent += 1;
+                }
+                if exponent < bytes.len() && bytes[exponent].is_ascii_digit() {
+                    end = digits(exponent);
+                }
+            }
+            if end == 0 {
+                return Ok(None);
+            }
+            let number = self.rest[..end]
+                .parse()
+                .map_err(|_| format!("{} isn't a number", &self.rest[..end]))?;
+            self.rest = &self.rest[end..];
+            Ok(Some(number))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn vertices(points: &[(f64, f64)]) -> Vec<Point> {
+            points.iter().map(|&(x, y)| Point::new(x, y)).collect()
+        }
+
+        #[test]
+        fn polygons_are_read_with_y_flipped() {
+            let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+                <polygon fill="none" points="0,0 100,0 50,-100"/>
+            </svg>"#;
+            assert_eq!(
+                parse(svg).unwrap(),
+                vertices(&[(0.0, 0.0), (100.0, 0.0), (50.0, 100.0)])
+            );
+        }
+
+        #[test]
+        fn paths_follow_moveto_and_lineto() {
+            let svg = "<path id='d' d='M10 10 L 20,10 l0-10 h5 Z'/>";
+            assert!(parse(svg).is_err());
+
+            let svg = "<path id='d' d='M10 10 20,10 l0-10-10-.5.5.5Z'/>";
+            assert_eq!(
+                parse(svg).unwrap(),
+                vertices(&[
+                    (10.0, -10.0),
+                    (20.0, -10.0),
+                    (20.0, 0.0),
+                    (10.0, 0.5),
+                    (10.5, 0.0)
+                ])
+            );
+        }
+
+        #[test]
+        fn the_first_shape_is_read() {
+            let svg = "<path d='M0 0 L1 0 L0 1 Z'/><polygon points='0,0 2,0 0,2'/>";
+            assert_eq!(
+                parse(svg).unwrap(),
+                vertices(&[(0.0, 0.0), (1.0, 0.0), (0.0, -1.0)])
+            );
+        }
+
+        #[test]
+        fn unusable_shapes_are_errors() {
+            for bad in &[
+                "<svg></svg>",
+                "<polygon points='0,0 1,0'/>",
+                "<polygon points='0,0 1,0 1'/>",
+                "<path d='M0 0 C1 1 2 2 3 3'/>",
+                "<path d='M0 0 L1 0 L1 1 Z M5 5 L6 5 L6 6 Z'/>",
+                "<pathway d='M0 0 L1 0 L1 1'/>",
+            ] {
+                assert!(parse(bad).is_err(), "{}", bad);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Point3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Point3 {
+    fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    fn jump_towards(&self, other: &Self, distance: f64) -> Self {
+        let x = (self.x * (1.0 - distance)) + (other.x * distance);
+        let y = (self.y * (1.0 - distance)) + (other.y * distance);
+        let z = (self.z * (1.0 - distance)) + (other.z * distance);
+        Self { x, y, z }
+    }
+
+    /// Rotates counterclockwise around the x axis when looking down it towards the origin
+    fn rotate_x(&self, radians: f64) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        let y = self.y * cos - self.z * sin;
+        let z = self.y * sin + self.z * cos;
+        Self { x: self.x, y, z }
+    }
+
+    /// Rotates counterclockwise around the y axis when looking down it towards the origin
+    fn rotate_y(&self, radians: f64) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        let x = self.x * cos + self.z * sin;
+        let z = -self.x * sin + self.z * cos;
+        Self { x, y: self.y, z }
+    }
+
+    /// Rotates counterclockwise around the z axis when looking down it towards the origin
+    fn rotate_z(&self, radians: f64) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        let x = self.x * cos - self.y * sin;
+        let y = self.x * sin + self.y * cos;
+        Self { x, y, z: self.z }
+    }
+}
+
+/// The smallest axis aligned box containing a set of points
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+#[allow(unused)]
+impl Bounds {
+    /// Panics if `points` is empty
+    fn of(points: &[Point]) -> Self {
+        let first = *points.first().expect("Shouldn't be empty");
+        points
+            .iter()
+            .fold(Self::new(first, first), |bounds, point| {
+                let min = Point::new(bounds.min.x.min(point.x), bounds.min.y.min(point.y));
+                let max = Point::new(bounds.max.x.max(point.x), bounds.max.y.max(point.y));
+                Self::new(min, max)
+            })
+    }
+
+    fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    /// Maps `point` into the unit square so `min` lands on (0, 0) and `max` on (1, 1). A flat
+    /// dimension maps to 0.
+    fn normalize(&self, point: &Point) -> Point {
+        let scale = |value: f64, min: f64, extent: f64| {
+            if extent > 0.0 {
+                (value - min) / extent
+            } else {
+                0.0
+            }
+        };
+        let x = scale(point.x, self.min.x, self.width());
+        let y = scale(point.y, self.min.y, self.height());
+        Point::new(x, y)
+    }
+
+    /// The `(column, row)` of the cell holding `point` when a `columns` x `rows` grid is laid over
+    /// the bounds. Row 0 is along `min.y`. Points on the max edges, or outside the bounds, are
+    /// clamped into the outermost cells.
+    fn cell(&self, point: &Point, columns: usize, rows: usize) -> (usize, usize) {
+        let normalized = self.normalize(point);
+        let index = |value: f64, count: usize| {
+            let index = (value * count as f64).floor().max(0.0) as usize;
+            index.min(count - 1)
+        };
+        (index(normalized.x, columns), index(normalized.y, rows))
+    }
+}
+
+/// Whether `point` is inside the polygon with vertices `polygon` in order, by casting a ray to
+/// the right and counting the edges it crosses. Points on an edge count as inside, since that's
+/// where plenty of chaos game points land.
+fn polygon_contains(polygon: &[Point], point: &Point) -> bool {
+    let edges = polygon.iter().zip(polygon.iter().cycle().skip(1));
+    let mut inside = false;
+    for (a, b) in edges {
+        let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+        let scale = a.distance(b) * (a.distance(point) + 1.0);
+        let within = point.x >= a.x.min(b.x)
+            && point.x <= a.x.max(b.x)
+            && point.y >= a.y.min(b.y)
+            && point.y <= a.y.max(b.y);
+        if within && cross.abs() <= 1e-9 * scale {
+            return true;
+        }
+        if (a.y > point.y) != (b.y > point.y) {
+            let crossing = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < crossing {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// The vertices of the smallest convex polygon containing `points`, counterclockwise starting
+/// from the leftmost, using [Andrew's monotone
+/// chain](https://en.wikibooks.org/wiki/Algorithm_Implementation/Geometry/Convex_hull/Monotone_chain).
+/// Collinear points along an edge are left out.
+fn convex_hull(points: &[Point]) -> Vec<Point> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    // Positive when o -> a -> b turns counterclockwise
+    let cross =
+        |o: &Point, a: &Point, b: &Point| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+    let mut hull: Vec<Point> = Vec::with_capacity(sorted.len() + 1);
+    for pass in 0..2 {
+        let start = hull.len();
+        let ordered: Box<dyn Iterator<Item = &Point>> = if pass == 0 {
+            Box::new(sorted.iter())
+        } else {
+            Box::new(sorted.iter().rev())
+        };
+        for point in ordered {
+            while hull.len() >= start + 2
+                && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(*point);
+        }
+        // The last point of each chain is the first of the next
+        hull.pop();
+    }
+    hull
+}
+
+/// A shape the chaos game is played on. The methods that play it are left off trait objects, so
+/// `Box<dyn Polygon>` can describe the shape and `CompositePolygon` plays the game.
+trait Polygon {
+    fn points(&self) -> Vec<Point>;
+
+    #[allow(unused)]
+    fn bounds(&self) -> Bounds {
+        Bounds::of(&self.points())
+    }
+
+    /// The boundary of the shape as a ring of vertices, for telling whether a point is inside it.
+    /// The vertices themselves by default.
+    fn outline(&self) -> Vec<Point> {
+        self.points()
+    }
+
+    /// Assumes that adjacent points are next to each other in the array that comes out of points
+    fn medial_points(&self) -> Vec<Point> {
+        let points = self.points();
+
+        let mut new_points = Vec::with_capacity(points.len());
+        for (i, point) in self.points().iter().enumerate() {
+            let next_point_index = (i + 1) % points.len();
+            let next_point = points[next_point_index];
+
+            let medial_point = point.midpoint(&next_point);
+            new_points.push(medial_point);
+        }
+
+        new_points
+    }
+
+    /// Prints the x y coordinates of the generated fractal to stdout separated by a space. This can be fed into
+    /// gnuplot to see the resulting fractal. `jump_distance` is how far to jump towards the next
+    /// target and `next_target` is a closure returning the index of that target in `targets`.
+    ///
+    /// With `--convergence` it instead prints `iteration distance` rows, where distance is how far
+    /// each point landed from the nearest point before it. Watching that decay shows how quickly
+    /// the game settles onto the attractor. Earlier points are kept in a grid over the polygon so
+    /// each one only searches the cells around it.
+    fn chaos_game<R, F>(
+        &self,
+        options: &Options,
+        rng: &mut R,
+        jump_distance: f64,
+        targets: &[Point],
+        next_target: &mut F,
+    ) -> Result<(), ChaosError>
+    where
+        Self: Sized,
+        R: Rng,
+        F: FnMut(&mut R) -> usize,
+    {
+        if let Some(point) = options.contains {
+            let inside = polygon_contains(&self.outline(), &point);
+            println!("{}", if inside { "yes" } else { "no" });
+            return if inside {
+                Ok(())
+            } else {
+                Err(ChaosError::Outside)
+            };
+        }
+        // `--auto-iterations` can only settle on a count now the vertices are known
+        let fitted;
+        let options = if options.auto_iterations
+            && options.iterations.is_none()
+            && options.until_coverage.is_none()
+        {
+            let vertices = self.points().len();
+            fitted = Options {
+                iterations: Some(auto_iterations(vertices)),
+                ..options.clone()
+            };
+            diagnostic!(
+                "iterations: {} for {} vertices",
+                fitted.iterations(),
+                vertices
+            );
+            &fitted
+        } else {
+            options
+        };
+        if let Some(weights) = &options.start_weights {
+            let edges = self.medial_points().len();
+            if weights.len() != edges {
+                return Err(ChaosError::Invalid(format!(
+                    "--start-weights needs one weight per edge, {} rather than {}",
+                    edges,
+                    weights.len()
+                )));
+            }
+            WeightedIndex::new(weights)
+                .map_err(|err| ChaosError::Invalid(format!("--start-weights: {}", err)))?;
+        }
+        let jump_distances = options.jump_distances(jump_distance);
+        if let Some(path) = &options.meta {
+            write_meta(path, options, &self.points(), jump_distances.distances())
+                .map_err(ChaosError::in_file(path))?;
+        }
+        // `--target-set` swaps the fractal's own targets for a uniform pick from the set
+        let target_set = options.target_set.map(|set| set.points(self));
+        let targets = target_set.as_deref().unwrap_or(targets);
+        if let Some(expr) = &options.restrict_expr {
+            let count = targets.len();
+            let stuck = (0..count)
+                .find(|&previous| !(0..count).any(|target| expr.allows(previous, target, count)));
+            if let Some(previous) = stuck {
+                return Err(ChaosError::Invalid(format!(
+                    "--restrict-expr turns down every target after target {}, it can't be \
+                     satisfied",
+                    previous
+                )));
+            }
+        }
+        // `--emit-selection` logs each target picked, as a trace to check the picking rule against
+        let mut selection = match &options.emit_selection {
+            Some(path) => Some((
+                path,
+                BufWriter::new(std::fs::File::create(path).map_err(ChaosError::in_file(path))?),
+            )),
+            None => None,
+        };
+        let mut output = Output::new(options, &self.points())?;
+        if options.clip_to_polygon {
+            output.clip = Some(self.outline());
+        }
+        let mut history = PriorPoints::new(Bounds::of(&self.points()), options.iterations());
+        let mut previous_point = None;
+        let mut i: usize = 0;
+
+        // `--orbit` adds a point circling the centroid as one more target to pick from
+        let center = Point::centroid(&self.points());
+        let orbit = options.orbit;
+        // `--restrict-expr` filters uniform picks by an expression over the previous pick
+        let restriction = options.restrict_expr.as_ref();
+        let mut previous = None;
+        let mut next = |rng: &mut R, iteration: usize| match orbit {
+            Some((radius, speed)) if rng.gen_range(0, targets.len() + 1) == targets.len() => {
+                let angle = speed * iteration as f64;
+                let (sin, cos) = angle.sin_cos();
+                let orbiting = Point::new(center.x + radius * cos, center.y + radius * sin);
+                (orbiting, Some(targets.len()))
+            }
+            _ => {
+                let target = match (restriction, previous) {
+                    // Some target is allowed after every previous one, so this always ends
+                    (Some(expr), Some(previous)) => {
+                        std::iter::repeat_with(|| rng.gen_range(0, targets.len()))
+                            .find(|&candidate| expr.allows(previous, candidate, targets.len()))
+                            .expect("The restriction was checked to be satisfiable")
+                    }
+                    (Some(_), None) => rng.gen_range(0, targets.len()),
+                    (None, _) => match target_set {
+                        Some(_) => rng.gen_range(0, targets.len()),
+                        None => next_target(rng),
+                    },
+                };
+                previous = Some(target);
+                (targets[target], Some(target))
+            }
+        };
+
+        let colorize = options
+            .color
+            .map(|scheme| scheme.colorize(options, &self.points()));
+        // `--selection-histogram` tallies the picks, with room for `--orbit`'s target
+        let mut selections = if options.selection_histogram {
+            Some(vec![0_u64; targets.len() + usize::from(orbit.is_some())])
+        } else {
+            None
+        };
+        // A resumed game starts from a point that's already been written
+        let resumed = options.resume_from.is_some();
+        let mut visit = |point: Point, target: Option<usize>| -> Result<bool, ChaosError> {
+            if resumed && target.is_none() {
+                return Ok(true);
+            }
+            if let (Some((path, selection)), Some(target)) = (&mut selection, target) {
+                writeln!(selection, "{}", target).map_err(ChaosError::in_file(path))?;
+            }
+            if let (Some(selections), Some(target)) = (&mut selections, target) {
+                selections[target] += 1;
+            }
+            if options.convergence {
+                let nearest = history.nearest(&point);
+                // `--every` thins the rows by the iteration they measure, as it would the points
+                if i > 0 && i.is_multiple_of(options.every) {
+                    output.emit_row(format!("{} {}", i, nearest))?;
+                }
+                history.insert(point);
+            } else {
+                let mut columns = Vec::new();
+                match options.value_column {
+                    None => {}
+                    Some(ValueColumn::Index) => columns.push(i as f64),
+                    Some(ValueColumn::Distance) => columns
+                        .push(previous_point.map_or(0.0, |previous| point.distance(&previous))),
+                    Some(ValueColumn::Vertex) => {
+                        columns.push(target.map_or(-1.0, |target| target as f64))
+                    }
+                }
+                if let Some(colorize) = &colorize {
+                    columns.push(colorize.column(&point, i, target));
+                }
+                output.emit(point, &columns)?;
+            }
+            previous_point = Some(point);
+            i += 1;
+            Ok(!output.is_full())
+        };
+
+        let (start, iterations) = match &options.resume_from {
+            Some(path) => (last_point(path)?, options.iterations() + 1),
+            None => (
+                self.start_point(rng, options.start_weights.as_deref()),
+                options.iterations(),
+            ),
+        };
+        if options.exact {
+            #[cfg(feature = "exact")]
+            self.play_exact(
+                rng,
+                start,
+                iterations,
+                &jump_distances,
+                &mut next,
+                &mut visit,
+            )?;
+            #[cfg(not(feature = "exact"))]
+            unreachable!("Options::check turns down --exact without the feature");
+        } else {
+            self.play_towards(
+                rng,
+                start,
+                iterations,
+                &jump_distances,
+                &mut next,
+                &mut visit,
+            )?;
+        }
+        output.finish()?;
+        if let Some((path, mut selection)) = selection {
+            selection.flush().map_err(ChaosError::in_file(path))?;
+        }
+        if let Some(selections) = selections {
+            let total = selections.iter().sum::<u64>().max(1);
+            for (target, count) in selections.iter().enumerate() {
+                diagnostic!(
+                    "target {}: {} ({:.1}%)",
+                    target,
+                    count,
+                    100.0 * *count as f64 / total as f64
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the points of the game instead of printing them
+    #[allow(unused)]
+    fn run<R, F>(
+        &self,
+        rng: &mut R,
+        iterations: usize,
+        jump_distance: f64,
+        targets: &[Point],
+        next_target: &mut F,
+    ) -> Vec<Point>
+    where
+        Self: Sized,
+        R: Rng,
+        F: FnMut(&mut R) -> usize,
+    {
+        let mut points = Vec::with_capacity(iterations);
+        self.play(
+            rng,
+            iterations,
+            jump_distance,
+            targets,
+            next_target,
+            &mut |point, _| {
+                points.push(point);
+                true
+            },
+        );
+        points
+    }
+
+    /// A random medial point to start the game from, picked in proportion to `weights` when given
+    /// and uniformly otherwise. Panics unless there's one weight per medial point.
+    fn start_point<R: Rng>(&self, rng: &mut R, weights: Option<&[f64]>) -> Point
+    where
+        Self: Sized,
+    {
+        let medial_points = self.medial_points();
+        match weights {
+            Some(weights) => {
+                assert_eq!(
+                    weights.len(),
+                    medial_points.len(),
+                    "--start-weights needs one weight per edge"
+                );
+                let index = WeightedIndex::new(weights)
+                    .unwrap_or_else(|err| panic!("--start-weights: {}", err));
+                medial_points[index.sample(rng)]
+            }
+            None => *medial_points.choose(rng).expect("Shouldn't be empty"),
+        }
+    }
+
+    /// Plays `iterations` rounds starting from a random medial point. `visit` is handed every
+    /// point along with the index of the target jumped towards to reach it, `None` for the
+    /// starting point, and returns whether to keep playing. All of the randomness comes from `rng`
+    /// so a seeded `rng` replays the same game.
+    fn play<R, F, V>(
+        &self,
+        rng: &mut R,
+        iterations: usize,
+        jump_distance: f64,
+        targets: &[Point],
+        next_target: &mut F,
+        visit: &mut V,
+    ) where
+        Self: Sized,
+        R: Rng,
+        F: FnMut(&mut R) -> usize,
+        V: FnMut(Point, Option<usize>) -> bool,
+    {
+        let start = self.start_point(rng, None);
+        let Ok(()) = self.play_towards::<_, _, _, std::convert::Infallible>(
+            rng,
+            start,
+            iterations,
+            &JumpDistances::Cycle(vec![jump_distance]),
+            &mut |rng, _| {
+                let target = next_target(rng);
+                (targets[target], Some(target))
+            },
+            &mut |point, target| Ok(visit(point, target)),
+        );
+    }
+
+    /// Like `play` but starting from `start`, and the target doesn't have to be one of a fixed set
+    /// of points. `next_target` is handed the iteration and returns the point to jump towards,
+    /// along with its index if it belongs to a set of targets. Each jump goes as far as
+    /// `jump_distances` says for its iteration. The game stops at the first error `visit` returns.
+    fn play_towards<R, F, V, E>(
+        &self,
+        rng: &mut R,
+        start: Point,
+        iterations: usize,
+        jump_distances: &JumpDistances,
+        next_target: &mut F,
+        visit: &mut V,
+    ) -> Result<(), E>
+    where
+        Self: Sized,
+        R: Rng,
+        F: FnMut(&mut R, usize) -> (Point, Option<usize>),
+        V: FnMut(Point, Option<usize>) -> Result<bool, E>,
+    {
+        let mut current_point = start;
+        let mut last_target = None;
+
+        for i in 0..iterations {
+            if !visit(current_point, last_target)? {
+                break;
+            }
+            let (target, index) = next_target(rng, i);
+            let jump_distance = jump_distances.next(rng, i);
+            current_point = current_point.jump_towards(&target, jump_distance);
+            last_target = index;
+        }
+        Ok(())
+    }
+
+    /// Like `play_towards` but tracks the point with exact fractions, only converting to floats to
+    /// hand it to `visit`. Only the midpoint fractals (every jump distance 0.5) are supported.
+    ///
+    /// Each halving adds a bit to the denominator so this is much slower than `play_towards` and
+    /// overflows after roughly 120 iterations, which is an error rather than losing precision.
+    /// It's meant for short runs that go deeper than the 53 bits a float can hold.
+    #[cfg(feature = "exact")]
+    fn play_exact<R, F, V>(
+        &self,
+        rng: &mut R,
+        start: Point,
+        iterations: usize,
+        jump_distances: &JumpDistances,
+        next_target: &mut F,
+        visit: &mut V,
+    ) -> Result<(), ChaosError>
+    where
+        Self: Sized,
+        R: Rng,
+        F: FnMut(&mut R, usize) -> (Point, Option<usize>),
+        V: FnMut(Point, Option<usize>) -> Result<bool, ChaosError>,
+    {
+        if !jump_distances.distances().iter().all(|&jump| jump == 0.5) {
+            return Err(ChaosError::Invalid(
+                "--exact only supports fractals that jump halfway".to_string(),
+            ));
+        }
+        let mut current_point = exact::ExactPoint::from_point(start);
+        let mut last_target = None;
+
+        for i in 0..iterations {
+            if !visit(current_point.to_point(), last_target)? {
+                break;
+            }
+            let (target, index) = next_target(rng, i);
+            current_point = current_point
+                .midpoint(&exact::ExactPoint::from_point(target))
+                .ok_or_else(|| {
+                    ChaosError::Invalid(format!("--exact overflowed after {} iterations", i + 1))
+                })?;
+            last_target = index;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Triangle {
+    pub a: Point,
+    pub b: Point,
+    pub c: Point,
+}
+
+impl Triangle {
+    pub fn new(a: Point, b: Point, c: Point) -> Self {
+        Self { a, b, c }
+    }
+
+    fn new_equilateral(length: f64) -> Self {
+        let a = Point::new(0.0, 0.0);
+        let b = Point::new(length, 0.0);
+        let c = Point::new(length / 2.0, length);
+        Triangle::new(a, b, c)
+    }
+}
+
+impl Polygon for Triangle {
+    fn points(&self) -> Vec<Point> {
+        vec![self.a, self.b, self.c]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Quadrilateral {
+    pub a: Point,
+    pub b: Point,
+    pub c: Point,
+    pub d: Point,
+}
+
+impl Quadrilateral {
+    pub fn new(a: Point, b: Point, c: Point, d: Point) -> Self {
+        Self { a, b, c, d }
+    }
+
+    fn square(length: f64) -> Self {
+        let a = Point::new(0.0, 0.0);
+        let b = Point::new(length, 0.0);
+        let c = Point::new(length, length);
+        let d = Point::new(0.0, length);
+        Self::new(a, b, c, d)
+    }
+}
+
+impl Polygon for Quadrilateral {
+    fn points(&self) -> Vec<Point> {
+        vec![self.a, self.b, self.c, self.d]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct RegularPolygon {
+    pub sides: usize,
+    pub radius: f64,
+    pub center: Point,
+    /// Radians counterclockwise from the x axis to the first vertex
+    pub start_angle: f64,
+}
+
+impl RegularPolygon {
+    pub fn new(sides: usize, radius: f64, center: Point) -> Self {
+        Self {
+            sides,
+            radius,
+            center,
+            start_angle: 0.0,
+        }
+    }
+
+    /// Turns the polygon so its first vertex is `radians` counterclockwise from directly right of
+    /// the center, e.g. pi/2 to point it up
+    pub fn with_start_angle(mut self, radians: f64) -> Self {
+        self.start_angle = radians;
+        self
+    }
+
+    /// Centered on the origin, so half of the fractal has negative coordinates
+    #[allow(unused)]
+    pub fn centered(sides: usize, radius: f64) -> Self {
+        Self::new(sides, radius, Point::new(0.0, 0.0))
+    }
+}
+
+impl Polygon for RegularPolygon {
+    /// The first vertex is `start_angle` around from directly right of the center, the rest
+    /// follow counterclockwise
+    fn points(&self) -> Vec<Point> {
+        (0..self.sides)
+            .map(|i| {
+                let angle =
+                    self.start_angle + 2.0 * std::f64::consts::PI * i as f64 / self.sides as f64;
+                let x = self.center.x + self.radius * angle.cos();
+                let y = self.center.y + self.radius * angle.sin();
+                Point::new(x, y)
+            })
+            .collect()
+    }
+}
+
+/// Several shapes played as one, e.g. a triangle inside a square, for hybrid attractors. Its
+/// vertices are every part's vertices in turn. Adjacency only means something within a part, so
+/// its medial points are each part's own rather than midpoints between parts, and fractals built
+/// on it should jump towards the vertices rather than a `--target-set` of medial points. For the
+/// same reason the vertices in turn aren't a ring around it, so its outline is their convex hull.
+struct CompositePolygon(Vec<Box<dyn Polygon>>);
+
+impl Polygon for CompositePolygon {
+    fn points(&self) -> Vec<Point> {
+        self.0.iter().flat_map(|part| part.points()).collect()
+    }
+
+    fn medial_points(&self) -> Vec<Point> {
+        self.0
+            .iter()
+            .flat_map(|part| part.medial_points())
+            .collect()
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        convex_hull(&self.points())
+    }
+}
+
+/// A polygon with any vertices, given in order around its edge, for shapes that aren't known
+/// until runtime like the ones read with `--svg-input`
+#[derive(Debug, Clone, PartialEq)]
+struct GenericPolygon {
+    pub points: Vec<Point>,
+}
+
+impl GenericPolygon {
+    fn new(points: Vec<Point>) -> Self {
+        Self { points }
+    }
+}
+
+impl Polygon for GenericPolygon {
+    fn points(&self) -> Vec<Point> {
+        self.points.clone()
+    }
+}
+
+/// The scale factor of each copy in an [n-flake](https://en.wikipedia.org/wiki/N-flake) with
+/// `sides` vertices, i.e. `1 / (2 * (1 + sum(cos(2 * pi * k / n) for k in 1..=n / 4)))`. Copies
+/// placed at the vertices with this ratio touch without overlapping.
+fn nflake_ratio(sides: usize) -> f64 {
+    let n = sides as f64;
+    let overlap: f64 = (1..=sides / 4)
+        .map(|k| (2.0 * std::f64::consts::PI * k as f64 / n).cos())
+        .sum();
+    1.0 / (2.0 * (1.0 + overlap))
+}
+
+/// The map `(x, y) -> (a x + b y + e, c x + d y + f)`, in the coefficient order [iterated
+/// function system](https://en.wikipedia.org/wiki/Iterated_function_system) tables use
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct AffineTransform {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+}
+
+impl AffineTransform {
+    fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    fn apply(&self, point: &Point) -> Point {
+        Point::new(
+            self.a * point.x + self.b * point.y + self.e,
+            self.c * point.x + self.d * point.y + self.f,
+        )
+    }
+}
+
+/// Coefficients of fern species for `barnsley-fern --variant`, one `a, b, c, d, e, f, weight` row
+/// per transform as in `--config`. The variants are from the [Barnsley fern
+/// article](https://en.wikipedia.org/wiki/Barnsley_fern#Mutant_varieties). In every one the
+/// first transform draws the stem, the second shrinks the whole frond up into its top, and the
+/// last two make the first leaflets on each side.
+const FERNS: [(&str, [[f64; 7]; 4]); 3] = [
+    (
+        "barnsley",
+        [
+            [0.0, 0.0, 0.0, 0.16, 0.0, 0.0, 0.01],
+            [0.85, 0.04, -0.04, 0.85, 0.0, 1.6, 0.85],
+            [0.2, -0.26, 0.23, 0.22, 0.0, 1.6, 0.07],
+            [-0.15, 0.28, 0.26, 0.24, 0.0, 0.44, 0.07],
+        ],
+    ),
+    (
+        // A Cyclosorus fern from the Thelypteridaceae family
+        "thelypteridaceae",
+        [
+            [0.0, 0.0, 0.0, 0.25, 0.0, -0.4, 0.02],
+            [0.95, 0.005, -0.005, 0.93, -0.002, 0.5, 0.84],
+            [0.035, -0.2, 0.16, 0.04, -0.09, 0.02, 0.07],
+            [-0.04, 0.2, 0.16, 0.04, 0.083, 0.12, 0.07],
+        ],
+    ),
+    (
+        // A Culcita fern
+        "leptosporangiate",
+        [
+            [0.0, 0.0, 0.0, 0.25, 0.0, -0.14, 0.02],
+            [0.85, 0.02, -0.02, 0.83, 0.0, 1.0, 0.84],
+            [0.09, -0.28, 0.3, 0.11, 0.0, 0.6, 0.07],
+            [-0.09, 0.28, 0.3, 0.09, 0.0, 0.7, 0.07],
+        ],
+    ),
+];
+
+/// A row of `FERNS`, picked by name or number with `--variant`
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Fern(usize);
+
+impl std::str::FromStr for Fern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FERNS
+            .iter()
+            .enumerate()
+            .position(|(i, (name, _))| *name == s || i.to_string() == s)
+            .map(Fern)
+            .ok_or_else(|| {
+                let names: Vec<&str> = FERNS.iter().map(|(name, _)| *name).collect();
+                format!("{} is not a fern, try one of {}", s, names.join(", "))
+            })
+    }
+}
+
+/// The transforms of an iterated function system and how often each is picked relative to the
+/// others
+#[derive(Debug, Clone, PartialEq)]
+struct Ifs {
+    transforms: Vec<AffineTransform>,
+    weights: Vec<f64>,
+}
+
+impl Ifs {
+    /// The [Barnsley fern](https://en.wikipedia.org/wiki/Barnsley_fern)
+    fn barnsley_fern() -> Self {
+        Self::fern(Fern(0))
+    }
+
+    fn fern(fern: Fern) -> Self {
+        let (_, table) = FERNS[fern.0];
+        Self {
+            transforms: table
+                .iter()
+                .map(|&[a, b, c, d, e, f, _]| AffineTransform::new(a, b, c, d, e, f))
+                .collect(),
+            weights: table.iter().map(|row| row[6]).collect(),
+        }
+    }
+
+    /// Parses a config file with one `transform = a, b, c, d, e, f, weight` line per transform.
+    /// Blank lines and lines starting with `#` are skipped.
+    fn parse_config(text: &str) -> Result<Self, String> {
+        let mut ifs = Self {
+            transforms: Vec::new(),
+            weights: Vec::new(),
+        };
+        for (number, line) in text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+        {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.find('=') {
+                Some(index) => (line[..index].trim(), line[index + 1..].trim()),
+                None => return Err(format!("line {}: expected key = value", number)),
+            };
+            if key != "transform" {
+                return Err(format!("line {}: unknown key {}", number, key));
+            }
+            let values = value
+                .split(',')
+                .map(|item| item.trim().parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| format!("line {}: {}", number, err))?;
+            match values[..] {
+                [a, b, c, d, e, f, weight] => {
+                    if values.iter().any(|value| !value.is_finite()) {
+                        return Err(format!("line {}: values must be finite", number));
+                    }
+                    if weight < 0.0 {
+                        return Err(format!("line {}: weight can't be negative", number));
+                    }
+                    ifs.transforms.push(AffineTransform::new(a, b, c, d, e, f));
+                    ifs.weights.push(weight);
+                }
+                _ => {
+                    return Err(format!(
+                        "line {}: a transform takes a, b, c, d, e, f, weight",
+                        number
+                    ))
+                }
+            }
+        }
+        if ifs.weights.iter().sum::<f64>() <= 0.0 {
+            return Err("the transform weights must add up to more than 0".to_string());
+        }
+        Ok(ifs)
+    }
+}
+
+const ITERATIONS: usize = 1000000;
+
+/// The iterations `--auto-iterations` picks for a polygon with `vertices` vertices, `ITERATIONS`
+/// for a triangle growing with the square of the vertex count
+fn auto_iterations(vertices: usize) -> usize {
+    (ITERATIONS as f64 * (vertices as f64 / 3.0).powi(2)).round() as usize
+}
+
+/// The `c` that `julia` draws without `--c`, the Douady rabbit
+const JULIA_C: (f64, f64) = (-0.123, 0.745);
+
+/// The semi-axes `ellipse-attractor` jumps towards without `--a` and `--b`
+const ELLIPSE_A: f64 = 50.0;
+const ELLIPSE_B: f64 = 30.0;
+
+/// Inverse iterations `julia` runs before writing points, so the start point has converged
+const JULIA_SETTLE: usize = 50;
+
+/// How many rows `pascal` computes unless `--rows` says otherwise
+const PASCAL_ROWS: usize = 64;
+
+/// The petal parameter and number of points `rose` uses unless `--k` and `--points` say otherwise
+const ROSE_K: f64 = 4.0;
+const ROSE_POINTS: usize = 1000;
+
+/// How many points `apollonian` puts on its outer circle unless `--points` says otherwise. The
+/// circles inside get fewer in proportion to their size.
+const APOLLONIAN_POINTS: usize = 1000;
+
+/// The fewest points `apollonian` puts on a circle however small it is
+const APOLLONIAN_MIN_POINTS: usize = 8;
+
+/// The deepest `--depth` `apollonian` goes. The circles triple with each level and are all held
+/// at once, so this is already nearly five million of them.
+const APOLLONIAN_MAX_DEPTH: usize = 12;
+
+/// The radius of the outer circle of `apollonian`
+const APOLLONIAN_RADIUS: f64 = 50.0;
+
+/// The order of the curve `hilbert` draws unless `--order` says otherwise
+const HILBERT_ORDER: u32 = 5;
+
+/// The radius of the wheel of triangles `penrose` starts from
+const PENROSE_RADIUS: f64 = 50.0;
+
+/// The deepest `--depth` `penrose` goes. The triangles grow by about 2.6 times with each round
+/// and are all held at once, so this is already around five million edges.
+const PENROSE_MAX_DEPTH: usize = 14;
+
+/// How many times `medial` takes the medial polygon unless `--medial-depth` says otherwise
+const MEDIAL_DEPTH: usize = 4;
+
+/// How far the petals of `rose` reach from the center
+const ROSE_RADIUS: f64 = 50.0;
+
+/// The largest denominator `rose` looks for when reading `--k` as a fraction. A `k` that isn't
+/// one of those, like an irrational one, never closes and is drawn as its nearest such fraction.
+const ROSE_MAX_DENOMINATOR: u64 = 1000;
+
+/// How many times `subdivide` replaces every edge unless `--depth` says otherwise
+const DEPTH: usize = 4;
+
+/// How many frames `--gif` splits the run into by default
+const FRAMES: usize = 60;
+
+/// Dots and page for `--svg` unless `--svg-color` and `--svg-background` say otherwise
+const SVG_COLOR: &str = "#222222";
+const SVG_BACKGROUND: &str = "white";
+
+/// Points `--verify` lets the game take to settle onto the fractal before checking its holes
+const VERIFY_SETTLE: usize = 50;
+
+/// How far `--verify` shrinks each hole towards its centroid, as a fraction of the way, so points
+/// on or right next to its edges aren't counted as in it
+const VERIFY_TOLERANCE: f64 = 0.01;
+
+/// The exit status after Ctrl-C, the shell's 128 plus SIGINT
+const INTERRUPTED_STATUS: i32 = 130;
+
+/// The cells along each side of the grid `--until-coverage` measures coverage on
+const COVERAGE_GRID: usize = 256;
+
+/// The space `--panels` leaves between panels, as a fraction of the largest fractal's size
+const PANEL_GAP: f64 = 0.1;
+
+/// The most iterations `--until-coverage` plays without `--iterations`
+const COVERAGE_ITERATIONS: usize = 100_000_000;
+
+/// The cells along each side of the grid `--decimate-adaptive` estimates density on
+const DECIMATE_GRID: usize = 128;
+
+/// How many points `--nn-stats` samples to measure nearest neighbor distances over
+const NN_SAMPLE: usize = 10000;
+
+/// Above this many iterations a trajectory is too dense for its line segments to mean anything
+const TRAJECTORY_LIMIT: usize = 1000;
+
+/// A number given either as a decimal or as an exact `N/D` fraction, e.g. `2/3`
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Fraction(f64);
+
+impl std::str::FromStr for Fraction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |part: &str| {
+            part.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("{} is not a number or fraction", s))
+        };
+        match s.find('/') {
+            Some(index) => {
+                let (numerator, denominator) = (parse(&s[..index])?, parse(&s[index + 1..])?);
+                if denominator == 0.0 {
+                    return Err(format!("{} divides by zero", s));
+                }
+                Ok(Fraction(numerator / denominator))
+            }
+            None => parse(s).map(Fraction),
+        }
+    }
+}
+
+impl Fraction {
+    /// Checks the fraction works as `--jump-distance`. It has to be more than 0 or every point
+    /// stays put, and less than 1 or the points overshoot their targets and fly off, unless
+    /// `allow_extrapolation` permits 1 and beyond.
+    fn jump_distance(self, allow_extrapolation: bool) -> Result<f64, String> {
+        match self.0 {
+            jump if jump.is_nan() => Err("is not a number".to_string()),
+            jump if jump <= 0.0 => Err(format!("{} is not more than 0", jump)),
+            jump if !jump.is_finite() => Err(format!("{} is not finite", jump)),
+            jump if jump >= 1.0 && !allow_extrapolation => Err(format!(
+                "{} is not less than 1, add --allow-extrapolation to jump past the target",
+                jump
+            )),
+            jump => Ok(jump),
+        }
+    }
+}
+
+/// One `D:P` of `--jump-distances`, a jump distance and the weight it's drawn with
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct WeightedJump {
+    distance: Fraction,
+    weight: f64,
+}
+
+impl std::str::FromStr for WeightedJump {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let index = s
+            .find(':')
+            .ok_or_else(|| format!("{} is not a distance:weight pair", s))?;
+        let distance = s[..index].parse()?;
+        let weight = s[index + 1..]
+            .trim()
+            .parse()
+            .map_err(|_| format!("{} is not a number", &s[index + 1..]))?;
+        Ok(WeightedJump { distance, weight })
+    }
+}
+
+/// How far each jump goes: cycling through the distances one per jump, or drawing each jump's
+/// distance at random in proportion to its weight
+#[derive(Debug, Clone)]
+enum JumpDistances {
+    Cycle(Vec<f64>),
+    Weighted(Vec<f64>, WeightedIndex<f64>),
+}
+
+impl JumpDistances {
+    /// The distance for jump number `iteration`, only drawing from `rng` when weighted
+    fn next<R: Rng>(&self, rng: &mut R, iteration: usize) -> f64 {
+        match self {
+            JumpDistances::Cycle(jumps) => jumps[iteration % jumps.len()],
+            JumpDistances::Weighted(jumps, weights) => jumps[weights.sample(rng)],
+        }
+    }
+
+    /// Every distance a jump might go
+    fn distances(&self) -> &[f64] {
+        match self {
+            JumpDistances::Cycle(jumps) | JumpDistances::Weighted(jumps, _) => jumps,
+        }
+    }
+}
+
+/// The points `--target-set` has the game jump towards instead of a fractal's own
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TargetSet {
+    /// The polygon's vertices
+    Vertices,
+    /// The midpoints of its edges
+    Medial,
+    /// Both, going around the polygon from the first vertex to the first midpoint and so on
+    Augmented,
+}
+
+impl TargetSet {
+    fn points<P: Polygon + ?Sized>(&self, polygon: &P) -> Vec<Point> {
+        match self {
+            TargetSet::Vertices => polygon.points(),
+            TargetSet::Medial => polygon.medial_points(),
+            TargetSet::Augmented => polygon
+                .points()
+                .into_iter()
+                .zip(polygon.medial_points())
+                .flat_map(|(vertex, medial)| vec![vertex, medial])
+                .collect(),
+        }
+    }
+}
+
+impl std::str::FromStr for TargetSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vertices" => Ok(TargetSet::Vertices),
+            "medial" => Ok(TargetSet::Medial),
+            "augmented" => Ok(TargetSet::Augmented),
+            other => Err(format!("{} is not vertices, medial, or augmented", other)),
+        }
+    }
+}
+
+/// What goes in the optional third output column, e.g. for `plot '...' with points palette`
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ValueColumn {
+    /// The iteration that produced the point
+    Index,
+    /// How far the point is from the one before it
+    Distance,
+    /// The index of the target that was jumped towards, -1 for the starting point
+    Vertex,
+}
+
+impl std::str::FromStr for ValueColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "index" => Ok(ValueColumn::Index),
+            "distance" => Ok(ValueColumn::Distance),
+            "vertex" => Ok(ValueColumn::Vertex),
+            other => Err(format!("{} is not a value column", other)),
+        }
+    }
+}
+
+/// A color for gnuplot's `lc rgb variable`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Rgb(u8, u8, u8);
+
+impl Rgb {
+    /// `0xRRGGBB`, the form gnuplot reads from a column
+    fn packed(&self) -> u32 {
+        (u32::from(self.0) << 16) | (u32::from(self.1) << 8) | u32::from(self.2)
+    }
+
+    /// Blends linearly from `self` at 0 to `other` at 1
+    fn blend(&self, other: &Self, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |from: u8, to: u8| {
+            (f64::from(from) + (f64::from(to) - f64::from(from)) * t).round() as u8
+        };
+        Rgb(
+            channel(self.0, other.0),
+            channel(self.1, other.1),
+            channel(self.2, other.2),
+        )
+    }
+}
+
+/// Picks where in the palette each generated point's color comes from. Implement this and add it
+/// to `ColorScheme` to make a new scheme available to `--color`.
+trait Colorize {
+    /// A value from 0 to 1 along the palette, or `None` to leave the point uncolored (black).
+    /// `vertex` is the target jumped towards to reach `point`, `None` for the starting point.
+    fn value(&self, point: &Point, iteration: usize, vertex: Option<usize>) -> Option<f64>;
+}
+
+/// A `--color` scheme together with the palette it picks from
+struct Coloring {
+    scheme: Box<dyn Colorize>,
+    palette: Palette,
+    /// With `--quantize-colors` the number of palette entries colors are rounded to
+    levels: Option<usize>,
+}
+
+impl Coloring {
+    fn color(&self, point: &Point, iteration: usize, vertex: Option<usize>) -> Rgb {
+        self.scheme
+            .value(point, iteration, vertex)
+            .map_or(Rgb(0, 0, 0), |t| self.palette.at(t))
+    }
+
+    /// The value of the color column: the packed color, or with `--quantize-colors` the index of
+    /// the nearest palette entry and -1 for an uncolored point
+    fn column(&self, point: &Point, iteration: usize, vertex: Option<usize>) -> f64 {
+        match self.levels {
+            Some(levels) => self
+                .scheme
+                .value(point, iteration, vertex)
+                .map_or(-1.0, |t| Palette::level(t, levels) as f64),
+            None => f64::from(self.color(point, iteration, vertex).packed()),
+        }
+    }
+}
+
+/// The gradients `--color-palette` can pick by name, each turning a value from 0 to 1 into a color
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+enum Palette {
+    #[default]
+    Viridis,
+    Magma,
+    Grayscale,
+    Rainbow,
+}
+
+impl Palette {
+    /// Evenly spaced stops of matplotlib's viridis, dark purple to yellow
+    const VIRIDIS: [Rgb; 9] = [
+        Rgb(0x44, 0x01, 0x54),
+        Rgb(0x47, 0x2d, 0x7b),
+        Rgb(0x3b, 0x52, 0x8b),
+        Rgb(0x2c, 0x72, 0x8e),
+        Rgb(0x21, 0x90, 0x8c),
+        Rgb(0x27, 0xad, 0x81),
+        Rgb(0x5d, 0xc8, 0x63),
+        Rgb(0xaa, 0xdc, 0x32),
+        Rgb(0xfd, 0xe7, 0x25),
+    ];
+
+    /// Evenly spaced stops of matplotlib's magma, black to pale yellow
+    const MAGMA: [Rgb; 9] = [
+        Rgb(0x00, 0x00, 0x04),
+        Rgb(0x1d, 0x11, 0x47),
+        Rgb(0x51, 0x12, 0x7c),
+        Rgb(0x82, 0x26, 0x81),
+        Rgb(0xb6, 0x36, 0x79),
+        Rgb(0xe6, 0x51, 0x64),
+        Rgb(0xfb, 0x88, 0x61),
+        Rgb(0xfe, 0xc2, 0x87),
+        Rgb(0xfc, 0xfd, 0xbf),
+    ];
+
+    const GRAYSCALE: [Rgb; 2] = [Rgb(0, 0, 0), Rgb(255, 255, 255)];
+
+    /// Red round the hue circle to magenta
+    const RAINBOW: [Rgb; 6] = [
+        Rgb(255, 0, 0),
+        Rgb(255, 255, 0),
+        Rgb(0, 255, 0),
+        Rgb(0, 255, 255),
+        Rgb(0, 0, 255),
+        Rgb(255, 0, 255),
+    ];
+
+    fn stops(&self) -> &'static [Rgb] {
+        match self {
+            Palette::Viridis => &Self::VIRIDIS,
+            Palette::Magma => &Self::MAGMA,
+            Palette::Grayscale => &Self::GRAYSCALE,
+            Palette::Rainbow => &Self::RAINBOW,
+        }
+    }
+
+    /// `levels` colors evenly spaced along the palette, the first and last at its ends
+    fn quantized(&self, levels: usize) -> Vec<Rgb> {
+        (0..levels)
+            .map(|level| self.at(level as f64 / levels.saturating_sub(1).max(1) as f64))
+            .collect()
+    }
+
+    /// The index of the nearest of `quantized(levels)` to the color at `t`
+    fn level(t: f64, levels: usize) -> usize {
+        (t.clamp(0.0, 1.0) * levels.saturating_sub(1) as f64).round() as usize
+    }
+
+    /// The color at `t`, blending between the two nearest stops. `t` is clamped to 0 to 1.
+    fn at(&self, t: f64) -> Rgb {
+        let stops = self.stops();
+        let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let below = (position.floor() as usize).min(stops.len() - 2);
+        stops[below].blend(&stops[below + 1], position - below as f64)
+    }
+}
+
+impl std::str::FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viridis" => Ok(Palette::Viridis),
+            "magma" => Ok(Palette::Magma),
+            "grayscale" => Ok(Palette::Grayscale),
+            "rainbow" => Ok(Palette::Rainbow),
+            other => Err(format!("{} is not a color palette", other)),
+        }
+    }
+}
+
+/// One color per vertex, spread evenly across the palette
+struct ByVertex {
+    vertices: usize,
+}
+
+impl Colorize for ByVertex {
+    fn value(&self, _: &Point, _: usize, vertex: Option<usize>) -> Option<f64> {
+        vertex.map(|vertex| {
+            let t = (vertex % self.vertices.max(1)) as f64;
+            t / self.vertices.saturating_sub(1).max(1) as f64
+        })
+    }
+}
+
+/// Runs through the palette over the run
+struct ByIteration {
+    iterations: usize,
+}
+
+impl Colorize for ByIteration {
+    fn value(&self, _: &Point, iteration: usize, _: Option<usize>) -> Option<f64> {
+        Some(iteration as f64 / self.iterations.saturating_sub(1).max(1) as f64)
+    }
+}
+
+/// Runs through the palette from `center` out to `radius` and beyond
+struct ByRadius {
+    center: Point,
+    radius: f64,
+}
+
+impl Colorize for ByRadius {
+    fn value(&self, point: &Point, _: usize, _: Option<usize>) -> Option<f64> {
+        if self.radius > 0.0 {
+            Some(point.distance(&self.center) / self.radius)
+        } else {
+            Some(0.0)
+        }
+    }
+}
+
+/// The coloring schemes `--color` can pick by name
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ColorScheme {
+    Vertex,
+    Iteration,
+    Radius,
+}
+
+impl ColorScheme {
+    /// `frame` is the shape the fractal is built on, schemes measure positions against it
+    fn colorize(&self, options: &Options, frame: &[Point]) -> Coloring {
+        let scheme: Box<dyn Colorize> = match self {
+            ColorScheme::Vertex => Box::new(ByVertex {
+                vertices: frame.len(),
+            }),
+            ColorScheme::Iteration => Box::new(ByIteration {
+                iterations: options.iterations(),
+            }),
+            ColorScheme::Radius => {
+                let center = Point::centroid(frame);
+                let radius = frame
+                    .iter()
+                    .map(|point| point.distance(&center))
+                    .fold(0.0, f64::max);
+                Box::new(ByRadius { center, radius })
+            }
+        };
+        Coloring {
+            scheme,
+            palette: options.color_palette,
+            levels: options.quantize_colors,
+        }
+    }
+}
+
+impl std::str::FromStr for ColorScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vertex" => Ok(ColorScheme::Vertex),
+            "iteration" => Ok(ColorScheme::Iteration),
+            "radius" => Ok(ColorScheme::Radius),
+            other => Err(format!("{} is not a color scheme", other)),
+        }
+    }
+}
+
+/// A change of coordinates applied to every point on its way out
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Transform {
+    /// Turns points counterclockwise by `radians` around `center`
+    Rotate { center: Point, radians: f64 },
+    /// Mirrors points to `sum - y`, where `sum` is the `min.y + max.y` of the box to flip within
+    InvertY { sum: f64 },
+    /// Mirrors points across the line through `center` parallel to `axis`
+    Reflect { axis: Axis, center: Point },
+    /// Any linear map plus a translation, from `--affine`
+    Affine(AffineTransform),
+}
+
+impl Transform {
+    fn apply(&self, point: Point) -> Point {
+        match *self {
+            Transform::Rotate { center, radians } => point
+                .translate(-center.x, -center.y)
+                .rotate(radians)
+                .translate(center.x, center.y),
+            Transform::InvertY { sum } => Point::new(point.x, sum - point.y),
+            Transform::Reflect { axis, center } => match axis {
+                Axis::X => Point::new(point.x, 2.0 * center.y - point.y),
+                Axis::Y => Point::new(2.0 * center.x - point.x, point.y),
+            },
+            Transform::Affine(affine) => affine.apply(&point),
+        }
+    }
+}
+
+/// The transforms asked for on the command line, applied one after another in a fixed order no
+/// matter the order of the flags:
+///
+/// 1. `--rotate-output`, around the centroid of the frame
+/// 2. `--invert-y`, within the box around the frame
+/// 3. `--affine`
+///
+/// Every transform is placed relative to the untransformed frame. Clipping happens before any of
+/// them and tiling, `--scale`, and `--integer` after all of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct TransformPipeline(Vec<Transform>);
+
+impl TransformPipeline {
+    fn new(options: &Options, frame: &[Point]) -> Self {
+        let mut transforms = Vec::new();
+        if let Some(degrees) = options.rotate_output {
+            transforms.push(Transform::Rotate {
+                center: Point::centroid(frame),
+                radians: degrees.to_radians(),
+            });
+        }
+        if options.invert_y {
+            let bounds = Bounds::of(frame);
+            transforms.push(Transform::InvertY {
+                sum: bounds.min.y + bounds.max.y,
+            });
+        }
+        if let Some(affine) = options.affine {
+            transforms.push(Transform::Affine(affine));
+        }
+        Self(transforms)
+    }
+
+    fn apply(&self, point: Point) -> Point {
+        self.0
+            .iter()
+            .fold(point, |point, transform| transform.apply(point))
+    }
+}
+
+/// Where a `tcp://HOST:PORT` or `unix://PATH` `--output` connects to, `None` for a file
+fn connect_address(output: &str) -> Option<(&str, &str)> {
+    ["tcp", "unix"].iter().find_map(|&scheme| {
+        output
+            .strip_prefix(scheme)
+            .and_then(|rest| rest.strip_prefix("://"))
+            .map(|address| (scheme, address))
+    })
+}
+
+/// Connects to a socket `--output`, or `None` if it names a file
+fn connect(output: &str) -> Option<io::Result<Box<dyn Write>>> {
+    let stream = match connect_address(output)? {
+        ("tcp", address) => std::net::TcpStream::connect(address)
+            .map(|stream| Box::new(BufWriter::new(stream)) as Box<dyn Write>),
+        #[cfg(unix)]
+        (_, path) => std::os::unix::net::UnixStream::connect(path)
+            .map(|stream| Box::new(BufWriter::new(stream)) as Box<dyn Write>),
+        #[cfg(not(unix))]
+        (_, _) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "unix sockets need a unix build",
+        )),
+    };
+    Some(stream)
+}
+
+/// Where generated points go. They're written to `--output` (stdout by default) straight away
+/// unless `--sample` asks for a reservoir or `--hull` needs all of them, in which case they're held
+/// until `finish`.
+struct Output {
+    /// More than one with `--split`, point rows are dealt out to them in turn
+    writers: Vec<Box<dyn Write>>,
+    sample: Option<Reservoir<String>>,
+    /// A sample of the points with `--nn-stats` to measure nearest neighbor distances over
+    nearest: Option<Reservoir<Point>>,
+    hull: Option<Vec<Point>>,
+    /// With `--with-hull` the points written so far, whose hull `finish` writes after them
+    with_hull: Option<Vec<Point>>,
+    /// The points counted into a grid with `--histogram`, written in their place by `finish`
+    histogram: Option<Histogram>,
+    /// The points and their extra columns with `--z-order`, sorted and written by `finish`
+    z_order: Option<Vec<(Point, Vec<f64>)>>,
+    tile: Option<Tiling>,
+    format: Format,
+    /// Written coordinates are multiplied by `scale` and with `integer` rounded to the nearest
+    /// whole number
+    scale: f64,
+    integer: bool,
+    /// With `--sig-figs` coordinates are written to this many significant figures
+    sig_figs: Option<usize>,
+    /// With `--delta` the coordinates last written, which the next row is written relative to
+    delta: Option<Option<(f64, f64)>>,
+    /// The centroid of the frame, which polar coordinates are measured from
+    center: Point,
+    max_points: Option<usize>,
+    /// Point rows written so far
+    written: usize,
+    /// With `--flush-every` the writers are flushed each time this many more rows are written
+    flush_every: Option<usize>,
+    /// Rows packed onto each line with `--points-per-line`, and how many the current line holds
+    points_per_line: usize,
+    on_line: usize,
+    /// With `--rate` the rows per second to write at most, and when the first row was written
+    rate: Option<(f64, Option<std::time::Instant>)>,
+    transforms: TransformPipeline,
+    /// The reflection with `--mirror`, each point is written a second time reflected before the
+    /// rest of the transforms
+    mirror: Option<Transform>,
+    svg: Option<Svg>,
+    /// Only every `every`th point handed to `emit` is kept, counting from the first
+    every: usize,
+    /// Points handed to `emit` so far
+    generated: usize,
+    decimation: Option<AdaptiveDecimation>,
+    /// With `--grid-unique` the cell size and the cells a point has already been written for
+    grid_unique: Option<(f64, std::collections::HashSet<(i64, i64)>)>,
+    non_finite: NonFinite,
+    /// The polygon's outline with `--clip-to-polygon`, set by `Polygon::chaos_game`, points
+    /// outside it are dropped
+    clip: Option<Vec<Point>>,
+    clipped: usize,
+    /// Points dropped for having NaN or infinite coordinates
+    skipped: usize,
+    /// With `--verify` the fractal's holes, shrunk by `VERIFY_TOLERANCE` so points on their
+    /// edges don't count, and how many points landed in them
+    holes: Option<Vec<Vec<Point>>>,
+    in_holes: usize,
+    coverage: Option<Coverage>,
+    /// With `--cell-count-check` the cells occupied on a grid of cells the size of the fractal's
+    /// copies `--depth` levels down, how it's made of copies, and the depth
+    cell_check: Option<(Coverage, SelfSimilarity, usize)>,
+    /// With `--summary-only` points are only counted and the stats are written in their place
+    summary_only: bool,
+    /// The x and y stats with `--stats`
+    stats: Option<(CoordinateStats, CoordinateStats)>,
+    #[cfg(feature = "gif")]
+    animation: Option<gif::Animation>,
+}
+
+/// Estimates a quantile of a stream in constant memory with the
+/// [P² algorithm](https://www.cse.wustl.edu/~jain/papers/ftp/psqr.pdf), which keeps five markers
+/// and nudges the middle three along a parabola as values arrive
+#[derive(Debug, Clone)]
+struct P2Quantile {
+    p: f64,
+    count: usize,
+    /// Marker heights, the middle one is the estimate
+    heights: [f64; 5],
+    /// Actual and desired marker positions, counting from 1
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    /// `p` is between 0 and 1, e.g. 0.5 for the median
+    fn new(p: f64) -> Self {
+        Self {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        if self.count < 5 {
+            self.heights[self.count] = value;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(|a, b| a.total_cmp(b));
+            }
+            return;
+        }
+        self.count += 1;
+
+        let cell = if value < self.heights[0] {
+            self.heights[0] = value;
+            0
+        } else if value >= self.heights[4] {
+            self.heights[4] = value;
+            3
+        } else {
+            (0..4)
+                .find(|&i| value < self.heights[i + 1])
+                .expect("value is below the last marker")
+        };
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(&self.increments) {
+            *desired += increment;
+        }
+
+        for i in 1..4 {
+            let offset = self.desired[i] - self.positions[i];
+            let (n, h) = (self.positions, self.heights);
+            if (offset >= 1.0 && n[i + 1] - n[i] > 1.0)
+                || (offset <= -1.0 && n[i - 1] - n[i] < -1.0)
+            {
+                let d = offset.signum();
+                let parabolic = h[i]
+                    + d / (n[i + 1] - n[i - 1])
+                        * ((n[i] - n[i - 1] + d) * (h[i + 1] - h[i]) / (n[i + 1] - n[i])
+                            + (n[i + 1] - n[i] - d) * (h[i] - h[i - 1]) / (n[i] - n[i - 1]));
+                self.heights[i] = if h[i - 1] < parabolic && parabolic < h[i + 1] {
+                    parabolic
+                } else {
+                    let j = if d > 0.0 { i + 1 } else { i - 1 };
+                    h[i] + d * (h[j] - h[i]) / (n[j] - n[i])
+                };
+                self.positions[i] += d;
+            }
+        }
+    }
+
+    /// The estimate so far, exact until there are more than five values. NaN if there are none.
+    fn estimate(&self) -> f64 {
+        if self.count >= 5 {
+            return self.heights[2];
+        }
+        if self.count == 0 {
+            return f64::NAN;
+        }
+        let mut seen = self.heights[..self.count].to_vec();
+        seen.sort_by(|a, b| a.total_cmp(b));
+        seen[((self.count - 1) as f64 * self.p).round() as usize]
+    }
+}
+
+/// The range and quartiles of one coordinate for `--stats`
+#[derive(Debug, Clone)]
+struct CoordinateStats {
+    min: f64,
+    max: f64,
+    quartiles: [P2Quantile; 3],
+}
+
+impl CoordinateStats {
+    fn new() -> Self {
+        Self {
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            quartiles: [
+                P2Quantile::new(0.25),
+                P2Quantile::new(0.5),
+                P2Quantile::new(0.75),
+            ],
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        for quartile in &mut self.quartiles {
+            quartile.add(value);
+        }
+    }
+
+    fn summary(&self) -> String {
+        let [first, median, third] = &self.quartiles;
+        let (first, third) = (first.estimate(), third.estimate());
+        format!(
+            "min {} max {} median {} quartiles {} {} (interquartile range {})",
+            self.min,
+            self.max,
+            median.estimate(),
+            first,
+            third,
+            third - first
+        )
+    }
+}
+
+/// Which cells of a square grid `side` cells across the frame have had a point land in them, for
+/// `--until-coverage` and `--cell-count-check`. Only the occupied cells are stored, since deep
+/// `--cell-count-check` grids have far more cells than points.
+struct Coverage {
+    bounds: Bounds,
+    side: usize,
+    occupied: std::collections::HashSet<(usize, usize)>,
+    count: usize,
+    /// The number of occupied cells to stop at
+    target: usize,
+}
+
+impl Coverage {
+    fn new(bounds: Bounds, side: usize, fraction: f64) -> Self {
+        Self {
+            bounds,
+            side,
+            occupied: std::collections::HashSet::new(),
+            count: 0,
+            target: (fraction * Self::cells(side)).ceil() as usize,
+        }
+    }
+
+    fn cells(side: usize) -> f64 {
+        side as f64 * side as f64
+    }
+
+    fn add(&mut self, point: &Point) {
+        if self
+            .occupied
+            .insert(self.bounds.cell(point, self.side, self.side))
+        {
+            self.count += 1;
+        }
+    }
+
+    fn reached(&self) -> bool {
+        self.count >= self.target
+    }
+
+    fn fraction(&self) -> f64 {
+        self.count as f64 / Self::cells(self.side)
+    }
+}
+
+/// Counts points into a square grid of cells laid over `bounds` for `--histogram`. Points outside
+/// `bounds` are counted in the nearest edge cell.
+struct Histogram {
+    bounds: Bounds,
+    cells: usize,
+    /// Row by row from `bounds.min.y`
+    counts: Vec<u64>,
+    binary: bool,
+}
+
+impl Histogram {
+    fn new(bounds: Bounds, cells: usize, binary: bool) -> Self {
+        Self {
+            bounds,
+            cells,
+            counts: vec![0; cells * cells],
+            binary,
+        }
+    }
+
+    fn add(&mut self, point: &Point) {
+        let (column, row) = self.bounds.cell(point, self.cells, self.cells);
+        self.counts[row * self.cells + column] += 1;
+    }
+
+    /// The center of each column or row along an axis running from `min` across `extent`
+    fn centers(&self, min: f64, extent: f64) -> Vec<f64> {
+        let size = extent / self.cells as f64;
+        (0..self.cells)
+            .map(|i| min + (i as f64 + 0.5) * size)
+            .collect()
+    }
+
+    /// As `x y count` rows with a blank line after each row of cells, gnuplot's grid layout, or
+    /// as gnuplot's binary matrix
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        if self.binary {
+            return writer.write_all(&self.binary_matrix());
+        }
+        let xs = self.centers(self.bounds.min.x, self.bounds.width());
+        let ys = self.centers(self.bounds.min.y, self.bounds.height());
+        for (y, counts) in ys.iter().zip(self.counts.chunks(self.cells)) {
+            for (x, count) in xs.iter().zip(counts) {
+                writeln!(writer, "{} {} {}", x, y, count)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// The layout `binary matrix` expects, all native endian 32 bit floats: the number of
+    /// columns followed by each column's x, then each row as its y followed by its counts
+    fn binary_matrix(&self) -> Vec<u8> {
+        let xs = self.centers(self.bounds.min.x, self.bounds.width());
+        let ys = self.centers(self.bounds.min.y, self.bounds.height());
+        let mut values = Vec::with_capacity((self.cells + 1) * (self.cells + 1));
+        values.push(self.cells as f32);
+        values.extend(xs.iter().map(|&x| x as f32));
+        for (y, counts) in ys.iter().zip(self.counts.chunks(self.cells)) {
+            values.push(*y as f32);
+            values.extend(counts.iter().map(|&count| count as f32));
+        }
+        values
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect()
+    }
+}
+
+/// Thins points by how crowded their neighborhood already is for `--decimate-adaptive`, counting
+/// the points seen in each cell of a `DECIMATE_GRID` square grid over `bounds`
+struct AdaptiveDecimation {
+    bounds: Bounds,
+    counts: Vec<u64>,
+    /// Every point is kept until its cell has seen this many
+    keep: usize,
+    rng: StdRng,
+}
+
+impl AdaptiveDecimation {
+    fn new(bounds: Bounds, keep: usize, rng: StdRng) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; DECIMATE_GRID * DECIMATE_GRID],
+            keep,
+            rng,
+        }
+    }
+
+    /// Counts `point` and decides whether to keep it, with probability `keep` over the points its
+    /// cell has seen
+    fn keeps(&mut self, point: &Point) -> bool {
+        let (column, row) = self.bounds.cell(point, DECIMATE_GRID, DECIMATE_GRID);
+        let count = &mut self.counts[row * DECIMATE_GRID + column];
+        *count += 1;
+        let probability = self.keep as f64 / *count as f64;
+        probability >= 1.0 || self.rng.gen_bool(probability)
+    }
+}
+
+/// Describes a polygon fractal's run as JSON for `--meta`: which fractal, the vertices it's built
+/// on and the box around them, the jump distance (a list when it cycles), the seed (`null` if
+/// there wasn't one), the iterations, the `--target-set` if the targets were swapped, and the rule
+/// targets are picked by (`null` for a uniform pick)
+fn write_meta(
+    path: &str,
+    options: &Options,
+    vertices: &[Point],
+    jump_distances: &[f64],
+) -> io::Result<()> {
+    let pair = |point: &Point| format!("[{}, {}]", point.x, point.y);
+    let bounds = Bounds::of(vertices);
+    let vertices: Vec<String> = vertices.iter().map(pair).collect();
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+    let fractal = options.command.as_deref().unwrap_or(DEFAULT_FRACTAL);
+    let target_set = options
+        .target_set
+        .map(|set| format!("\"{}\"", format!("{:?}", set).to_lowercase()));
+
+    let mut file = BufWriter::new(std::fs::File::create(path)?);
+    writeln!(file, "{{")?;
+    writeln!(file, "  \"fractal\": \"{}\",", fractal.escape_default())?;
+    writeln!(file, "  \"vertices\": [{}],", vertices.join(", "))?;
+    writeln!(
+        file,
+        "  \"bounds\": {{\"min\": {}, \"max\": {}}},",
+        pair(&bounds.min),
+        pair(&bounds.max)
+    )?;
+    let jump_distance = match jump_distances {
+        [jump] => jump.to_string(),
+        jumps => {
+            let jumps: Vec<String> = jumps.iter().map(f64::to_string).collect();
+            format!("[{}]", jumps.join(", "))
+        }
+    };
+    writeln!(file, "  \"jump_distance\": {},", jump_distance)?;
+    writeln!(
+        file,
+        "  \"seed\": {},",
+        optional(options.seed.map(|seed| seed.to_string()))
+    )?;
+    writeln!(file, "  \"iterations\": {},", options.iterations())?;
+    writeln!(file, "  \"target_set\": {},", optional(target_set))?;
+    // `--restrict-expr` replaces the fractal's own rule, and `--target-set` picks uniformly
+    let restriction = match (&options.restrict_expr, options.target_set) {
+        (Some(expr), _) => Some(expr.to_string()),
+        (None, Some(_)) => None,
+        (None, None) => options.restriction.map(str::to_string),
+    };
+    let restriction = restriction.map(|expr| format!("\"{}\"", expr.escape_default()));
+    writeln!(file, "  \"restriction\": {}", optional(restriction))?;
+    writeln!(file, "}}")?;
+    file.flush()
+}
+
+/// Draws the points as dots in an SVG document for `--svg`, written as they arrive
+struct Svg {
+    file: BufWriter<std::fs::File>,
+    /// `min.y + max.y` of the view, SVG's y grows downward so points are drawn at `flip - y`
+    flip: f64,
+    radius: f64,
+}
+
+impl Svg {
+    /// The view is `bounds` with a small margin, points outside it are cut off
+    fn create(
+        path: &str,
+        bounds: Bounds,
+        radius: Option<f64>,
+        color: &str,
+        background: &str,
+    ) -> io::Result<Self> {
+        let margin = bounds.width().max(bounds.height()) / 50.0;
+        let (x, y) = (bounds.min.x - margin, bounds.min.y - margin);
+        let (width, height) = (
+            bounds.width() + 2.0 * margin,
+            bounds.height() + 2.0 * margin,
+        );
+        let radius = radius.unwrap_or(bounds.width().max(bounds.height()) / 500.0);
+
+        let mut file = BufWriter::new(std::fs::File::create(path)?);
+        writeln!(
+            file,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+            x, y, width, height
+        )?;
+        writeln!(
+            file,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            x,
+            y,
+            width,
+            height,
+            escape_attribute(background)
+        )?;
+        writeln!(
+            file,
+            r#"<g fill="{}" stroke="none">"#,
+            escape_attribute(color)
+        )?;
+        Ok(Self {
+            file,
+            flip: bounds.min.y + bounds.max.y,
+            radius,
+        })
+    }
+
+    fn add(&mut self, point: &Point) -> io::Result<()> {
+        writeln!(
+            self.file,
+            r#"<circle cx="{}" cy="{}" r="{}"/>"#,
+            point.x,
+            self.flip - point.y,
+            self.radius
+        )
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        writeln!(self.file, "</g>")?;
+        writeln!(self.file, "</svg>")?;
+        self.file.flush()
+    }
+}
+
+/// `value` with the characters that would end or break out of a quoted XML attribute escaped
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// What to do with a point whose coordinates aren't finite numbers
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+enum NonFinite {
+    /// Leave it out and report how many were left out at the end
+    #[default]
+    Skip,
+    /// Stop with an error
+    Abort,
+}
+
+impl std::str::FromStr for NonFinite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(NonFinite::Skip),
+            "abort" => Ok(NonFinite::Abort),
+            other => Err(format!("{} is not skip or abort", other)),
+        }
+    }
+}
+
+/// The axis `--mirror` reflects across, through the centroid of the frame
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Axis {
+    /// Parallel to the x axis, so y is mirrored
+    X,
+    /// Parallel to the y axis, so x is mirrored
+    Y,
+}
+
+impl std::str::FromStr for Axis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x" => Ok(Axis::X),
+            "y" => Ok(Axis::Y),
+            other => Err(format!("{} is not x or y", other)),
+        }
+    }
+}
+
+/// How each point's coordinates are written
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+enum Format {
+    /// `x y`
+    #[default]
+    Cartesian,
+    /// `theta r` around the centroid of the shape the fractal is built on, with theta in radians
+    /// from -pi to pi
+    Polar,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cartesian" => Ok(Format::Cartesian),
+            "polar" => Ok(Format::Polar),
+            other => Err(format!("{} is not an output format", other)),
+        }
+    }
+}
+
+/// Where `--tile` puts the copies. Each is offset by the size of the shape the fractal is built
+/// on.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Tiling {
+    rows: usize,
+    columns: usize,
+    width: f64,
+    height: f64,
+}
+
+impl Output {
+    /// `frame` is the shape the fractal is built on, e.g. the vertices of its polygon. It's used to
+    /// place anything positioned relative to the fractal.
+    fn new(options: &Options, frame: &[Point]) -> Result<Self, ChaosError> {
+        let open = |path: &str| -> Result<Box<dyn Write>, ChaosError> {
+            if let Some(stream) = connect(path) {
+                return stream.map_err(|err| {
+                    ChaosError::Invalid(format!("couldn't connect to {}: {}", path, err))
+                });
+            }
+            let file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(options.append)
+                .truncate(!options.append)
+                .open(path)
+                .map_err(ChaosError::in_file(path))?;
+            Ok(Box::new(BufWriter::new(file)))
+        };
+        // `Options::check` has turned down `--split` without `--output` or to a socket
+        let writers: Vec<Box<dyn Write>> = match (&options.output, options.split) {
+            (Some(base), Some(files)) => (0..files)
+                .map(|i| open(&format!("{}.{:03}", base, i)))
+                .collect::<Result<_, _>>()?,
+            (Some(path), None) => vec![open(path)?],
+            (None, _) => vec![Box::new(BufWriter::new(io::stdout()))],
+        };
+
+        // Seeded off its own generator so sampling doesn't disturb the game's numbers
+        let seeded = || StdRng::from_rng(options.rng()).map_err(io::Error::from);
+        let mut output = Self {
+            writers,
+            sample: match options.sample {
+                Some(capacity) => Some(Reservoir::new(capacity, seeded()?)),
+                None => None,
+            },
+            nearest: if options.nn_stats {
+                Some(Reservoir::new(NN_SAMPLE, seeded()?))
+            } else {
+                None
+            },
+            hull: if options.hull { Some(Vec::new()) } else { None },
+            with_hull: if options.with_hull {
+                Some(Vec::new())
+            } else {
+                None
+            },
+            histogram: options
+                .histogram
+                .map(|cells| Histogram::new(Bounds::of(frame), cells, options.histogram_binary)),
+            z_order: if options.z_order {
+                Some(Vec::new())
+            } else {
+                None
+            },
+            tile: options.tile.map(|(rows, columns)| {
+                let bounds = Bounds::of(frame);
+                Tiling {
+                    rows,
+                    columns,
+                    width: bounds.width(),
+                    height: bounds.height(),
+                }
+            }),
+            format: options.format,
+            scale: options.scale.unwrap_or(1.0),
+            integer: options.integer,
+            sig_figs: options.sig_figs,
+            delta: if options.delta { Some(None) } else { None },
+            center: Point::centroid(frame),
+            max_points: options.max_points,
+            written: 0,
+            flush_every: options.flush_every,
+            points_per_line: options.points_per_line.unwrap_or(1),
+            on_line: 0,
+            rate: options.rate.map(|rate| (rate, None)),
+            transforms: TransformPipeline::new(options, frame),
+            mirror: options.mirror.map(|axis| Transform::Reflect {
+                axis,
+                center: Point::centroid(frame),
+            }),
+            every: options.every.max(1),
+            generated: 0,
+            decimation: match options.decimate_adaptive {
+                Some(keep) => Some(AdaptiveDecimation::new(Bounds::of(frame), keep, seeded()?)),
+                None => None,
+            },
+            grid_unique: options
+                .grid_unique
+                .filter(|&resolution| resolution > 0.0)
+                .map(|resolution| (resolution, std::collections::HashSet::new())),
+            non_finite: options.non_finite,
+            clip: None,
+            clipped: 0,
+            skipped: 0,
+            holes: if options.verify {
+                Some(
+                    options
+                        .holes
+                        .iter()
+                        .map(|hole| {
+                            let center = Point::centroid(hole);
+                            hole.iter()
+                                .map(|vertex| vertex.lerp(&center, VERIFY_TOLERANCE))
+                                .collect()
+                        })
+                        .collect(),
+                )
+            } else {
+                None
+            },
+            in_holes: 0,
+            coverage: options
+                .until_coverage
+                .map(|fraction| Coverage::new(Bounds::of(frame), COVERAGE_GRID, fraction)),
+            cell_check: if options.cell_count_check {
+                options.self_similarity.map(|similarity| {
+                    let side = similarity.side(options.depth);
+                    let cells = Coverage::new(Bounds::of(frame), side, 1.0);
+                    (cells, similarity, options.depth)
+                })
+            } else {
+                None
+            },
+            summary_only: options.summary_only,
+            stats: if options.stats || options.summary_only {
+                Some((CoordinateStats::new(), CoordinateStats::new()))
+            } else {
+                None
+            },
+            svg: options
+                .svg
+                .as_ref()
+                .map(|path| {
+                    Svg::create(
+                        path,
+                        Bounds::of(frame),
+                        options.svg_radius,
+                        &options.svg_color,
+                        &options.svg_background,
+                    )
+                    .map_err(ChaosError::in_file(path))
+                })
+                .transpose()?,
+            #[cfg(feature = "gif")]
+            animation: options
+                .gif
+                .as_ref()
+                .map(|path| {
+                    let every = options.iterations().div_ceil(options.frames);
+                    gif::Animation::create(path, Bounds::of(frame), every)
+                        .map_err(ChaosError::in_file(path))
+                })
+                .transpose()?,
+        };
+        if let (Some(_), Some(levels)) = (options.color, options.quantize_colors) {
+            if !options.summary_only {
+                let palette = options.color_palette.quantized(levels);
+                for writer in 0..output.writers.len() {
+                    for (level, color) in palette.iter().enumerate() {
+                        let line = format!("# color {} #{:06x}", level, color.packed());
+                        output.write(writer, &line)?;
+                    }
+                }
+            }
+        }
+        if options.trajectory && !options.summary_only {
+            for writer in 0..output.writers.len() {
+                output.write(writer, "# trajectory: plot with lines to follow the jumps")?;
+            }
+        }
+        Ok(output)
+    }
+
+    /// Emits a point as an `x y` row followed by any extra `columns`
+    fn emit(&mut self, point: Point, columns: &[f64]) -> Result<(), ChaosError> {
+        let index = self.generated;
+        self.generated += 1;
+        if let Some(holes) = &self.holes {
+            if index >= VERIFY_SETTLE && holes.iter().any(|hole| polygon_contains(hole, &point)) {
+                self.in_holes += 1;
+            }
+        }
+        if !index.is_multiple_of(self.every) {
+            return Ok(());
+        }
+        if !(point.x.is_finite() && point.y.is_finite()) {
+            match self.non_finite {
+                NonFinite::Skip => {
+                    self.skipped += 1;
+                    return Ok(());
+                }
+                NonFinite::Abort => {
+                    self.flush()?;
+                    return Err(ChaosError::Invalid(format!(
+                        "generated a point at ({}, {}), the fractal diverged",
+                        point.x, point.y
+                    )));
+                }
+            }
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.add(&point);
+        }
+        if let Some((cells, _, _)) = &mut self.cell_check {
+            cells.add(&point);
+        }
+        if let Some(polygon) = &self.clip {
+            if !polygon_contains(polygon, &point) {
+                self.clipped += 1;
+                return Ok(());
+            }
+        }
+        if let Some(decimation) = &mut self.decimation {
+            if !decimation.keeps(&point) {
+                return Ok(());
+            }
+        }
+        match self.mirror {
+            Some(reflect) => {
+                let group = |group: f64| -> Vec<f64> {
+                    columns.iter().copied().chain(Some(group)).collect()
+                };
+                let reflected = self.transforms.apply(reflect.apply(point));
+                self.emit_tiled(self.transforms.apply(point), &group(0.0))?;
+                self.emit_tiled(reflected, &group(1.0))
+            }
+            None => self.emit_tiled(self.transforms.apply(point), columns),
+        }
+    }
+
+    /// Emits a point once, or once per copy with `--tile`
+    fn emit_tiled(&mut self, point: Point, columns: &[f64]) -> Result<(), ChaosError> {
+        match self.tile {
+            Some(tile) => {
+                for row in 0..tile.rows {
+                    for column in 0..tile.columns {
+                        let dx = column as f64 * tile.width;
+                        let dy = row as f64 * tile.height;
+                        let group = (row * tile.columns + column) as f64;
+                        let columns: Vec<f64> =
+                            columns.iter().copied().chain(Some(group)).collect();
+                        self.emit_one(point.translate(dx, dy), &columns)?;
+                    }
+                }
+                Ok(())
+            }
+            None => self.emit_one(point, columns),
+        }
+    }
+
+    fn emit_one(&mut self, point: Point, columns: &[f64]) -> Result<(), ChaosError> {
+        if let Some((resolution, occupied)) = &mut self.grid_unique {
+            let cell = (
+                (point.x / *resolution).floor() as i64,
+                (point.y / *resolution).floor() as i64,
+            );
+            if !occupied.insert(cell) {
+                return Ok(());
+            }
+        }
+        if let Some((x, y)) = &mut self.stats {
+            x.add(point.x);
+            y.add(point.y);
+        }
+        if let Some(nearest) = &mut self.nearest {
+            nearest.offer(point);
+        }
+        if self.summary_only {
+            self.written += 1;
+            return Ok(());
+        }
+        #[cfg(feature = "gif")]
+        {
+            if let Some(animation) = &mut self.animation {
+                return animation.add(&point).map_err(ChaosError::Output);
+            }
+        }
+        if let Some(svg) = &mut self.svg {
+            return svg.add(&point).map_err(ChaosError::Output);
+        }
+        if let Some(points) = &mut self.hull {
+            points.push(point);
+            return Ok(());
+        }
+        if let Some(histogram) = &mut self.histogram {
+            histogram.add(&point);
+            return Ok(());
+        }
+        if let Some(points) = &mut self.z_order {
+            points.push((point, columns.to_vec()));
+            return Ok(());
+        }
+
+        let line = match &mut self.with_hull {
+            Some(points) => {
+                points.push(point);
+                let columns: Vec<f64> = columns.iter().copied().chain(Some(0.0)).collect();
+                self.format(point, &columns)
+            }
+            None => self.format(point, columns),
+        };
+        self.emit_row(line)
+    }
+
+    fn format(&mut self, point: Point, columns: &[f64]) -> String {
+        // Lengths are scaled and rounded, angles are left alone
+        let (scale, integer) = (self.scale, self.integer);
+        let length = |value: f64| {
+            let value = value * scale;
+            if integer {
+                value.round()
+            } else {
+                value
+            }
+        };
+        let (first, second) = match self.format {
+            Format::Cartesian => (length(point.x), length(point.y)),
+            Format::Polar => {
+                let (dx, dy) = (point.x - self.center.x, point.y - self.center.y);
+                (dy.atan2(dx), length(dx.hypot(dy)))
+            }
+        };
+        // Taken after rounding so summing the deltas lands exactly on the rounded coordinates
+        let (first, second) = match &mut self.delta {
+            Some(previous) => {
+                let (x, y) = previous.replace((first, second)).unwrap_or((0.0, 0.0));
+                (first - x, second - y)
+            }
+            None => (first, second),
+        };
+
+        let number = |value: f64| match self.sig_figs {
+            Some(figures) => significant_figures(value, figures),
+            None => format!("{}", value),
+        };
+        let length = |value: f64| {
+            if self.integer {
+                format!("{}", value as i64)
+            } else {
+                number(value)
+            }
+        };
+        let mut line = match self.format {
+            Format::Cartesian => format!("{} {}", length(first), length(second)),
+            Format::Polar => format!("{} {}", number(first), length(second)),
+        };
+        for column in columns {
+            line.push_str(&format!(" {}", column));
+        }
+        line
+    }
+
+    /// Ends a path drawn `with lines` with a blank row, which gnuplot doesn't join across. Only
+    /// written when points are written as they come, rather than gathered up or sampled.
+    fn break_path(&mut self) -> Result<(), ChaosError> {
+        let gathered = self.summary_only
+            || self.sample.is_some()
+            || self.svg.is_some()
+            || self.hull.is_some()
+            || self.histogram.is_some()
+            || self.z_order.is_some();
+        #[cfg(feature = "gif")]
+        let gathered = gathered || self.animation.is_some();
+        if !gathered {
+            self.end_line()?;
+            self.write(self.written % self.writers.len(), "")?;
+        }
+        Ok(())
+    }
+
+    /// Emits a row that isn't a point, like the `--convergence` measurements
+    fn emit_row(&mut self, line: String) -> Result<(), ChaosError> {
+        if self.summary_only {
+            self.written += 1;
+            return Ok(());
+        }
+        match &mut self.sample {
+            Some(reservoir) => {
+                reservoir.offer(line);
+                Ok(())
+            }
+            None => self.write_row(&line),
+        }
+    }
+
+    /// Whether there's no point generating more, because `--max-points` rows have been written,
+    /// `--until-coverage` has been reached, or the run was interrupted
+    fn is_full(&self) -> bool {
+        self.reached_max_points()
+            || self.coverage.as_ref().is_some_and(Coverage::reached)
+            || INTERRUPTED.load(Ordering::Relaxed)
+    }
+
+    fn reached_max_points(&self) -> bool {
+        self.max_points.is_some_and(|max| self.written >= max)
+    }
+
+    fn write_row(&mut self, line: &str) -> Result<(), ChaosError> {
+        if !self.reached_max_points() {
+            if self.points_per_line > 1 {
+                self.pack(line)?;
+            } else {
+                self.write(self.written % self.writers.len(), line)?;
+            }
+            self.written += 1;
+            if let Some(rows) = self.flush_every {
+                if self.written.is_multiple_of(rows) {
+                    self.flush()?;
+                }
+            }
+            self.throttle()?;
+        }
+        Ok(())
+    }
+
+    /// Writes a row onto the current line with `--points-per-line`, ending the line once it holds
+    /// that many rows
+    fn pack(&mut self, line: &str) -> Result<(), ChaosError> {
+        let separator = if self.on_line > 0 { " " } else { "" };
+        write!(self.writers[0], "{}{}", separator, line).map_err(ChaosError::Output)?;
+        self.on_line += 1;
+        if self.on_line == self.points_per_line {
+            self.end_line()?;
+        }
+        Ok(())
+    }
+
+    /// Ends a line `pack` left short
+    fn end_line(&mut self) -> Result<(), ChaosError> {
+        if self.on_line > 0 {
+            self.on_line = 0;
+            self.write(0, "")?;
+        }
+        Ok(())
+    }
+
+    /// Writes anything held back and flushes. Must be called once generation is done.
+    fn finish(mut self) -> Result<(), ChaosError> {
+        if self.skipped > 0 {
+            diagnostic!(
+                "warning: skipped {} points with NaN or infinite coordinates",
+                self.skipped
+            );
+        }
+        let mut summary = Vec::new();
+        if let Some((x, y)) = &self.stats {
+            summary.push(format!("x: {}", x.summary()));
+            summary.push(format!("y: {}", y.summary()));
+            if self.clip.is_some() {
+                summary.push(format!(
+                    "clipped: {} points outside the polygon",
+                    self.clipped
+                ));
+            }
+        }
+        if let Some(nearest) = self.nearest.take() {
+            let points: Vec<Point> = nearest.into_items().collect();
+            summary.push(nearest_neighbor_summary(&points));
+        }
+        if self.summary_only {
+            summary.insert(0, format!("points: {}", self.written));
+            for line in summary {
+                self.write(0, &line)?;
+            }
+        } else {
+            for line in summary {
+                diagnostic!("{}", line);
+            }
+        }
+        #[cfg(feature = "gif")]
+        {
+            if let Some(animation) = self.animation.take() {
+                animation.finish().map_err(ChaosError::Output)?;
+            }
+        }
+        if let Some(svg) = self.svg.take() {
+            svg.finish().map_err(ChaosError::Output)?;
+        }
+        if let Some(points) = self.hull.take() {
+            let hull = convex_hull(&points);
+            // Repeat the first vertex so `with lines` draws a closed polygon
+            for point in hull.iter().chain(hull.first()) {
+                let line = self.format(*point, &[]);
+                self.emit_row(line)?;
+            }
+        }
+        if let Some(points) = self.with_hull.take().filter(|points| !points.is_empty()) {
+            let hull = convex_hull(&points);
+            self.break_path()?;
+            for point in hull.iter().chain(hull.first()) {
+                let line = self.format(*point, &[1.0]);
+                self.emit_row(line)?;
+            }
+        }
+        if let Some(histogram) = self.histogram.take() {
+            histogram
+                .write(&mut self.writers[0])
+                .map_err(ChaosError::Output)?;
+        }
+        if let Some(mut points) = self.z_order.take().filter(|points| !points.is_empty()) {
+            let bounds = Bounds::of(&points.iter().map(|(point, _)| *point).collect::<Vec<_>>());
+            points.sort_by_cached_key(|(point, _)| morton_code(&bounds, point));
+            for (point, columns) in points {
+                let line = self.format(point, &columns);
+                self.emit_row(line)?;
+            }
+        }
+        if let Some(reservoir) = self.sample.take() {
+            for line in reservoir.into_items() {
+                self.write_row(&line)?;
+            }
+        }
+        if let Some(coverage) = &self.coverage {
+            let verb = if coverage.reached() {
+                "reached"
+            } else {
+                "only reached"
+            };
+            diagnostic!(
+                "{} {:.1}% coverage after {} points",
+                verb,
+                coverage.fraction() * 100.0,
+                self.generated
+            );
+        }
+        if let Some((cells, similarity, depth)) = &self.cell_check {
+            let expected = similarity.expected(*depth);
+            diagnostic!(
+                "cells: {} occupied of a {} x {} grid, {} in theory ({}^{}), a ratio of {:.3}",
+                cells.count,
+                cells.side,
+                cells.side,
+                expected,
+                similarity.copies,
+                depth,
+                cells.count as f64 / expected
+            );
+        }
+        if let Some(max) = self.max_points.filter(|_| self.reached_max_points()) {
+            diagnostic!("stopped after writing --max-points {} points", max);
+        }
+        self.end_line()?;
+        self.flush()?;
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            diagnostic!("interrupted, wrote {} rows before stopping", self.written);
+        }
+        if let Some(holes) = &self.holes {
+            if holes.is_empty() {
+                diagnostic!("verify: this fractal doesn't declare any holes to check");
+            } else if self.in_holes == 0 {
+                diagnostic!("verify: passed, no points in the {} holes", holes.len());
+            } else {
+                return Err(ChaosError::Invalid(format!(
+                    "verify: failed, {} points landed in the fractal's holes",
+                    self.in_holes
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// With `--rate`, sleeps until the rows written so far are due, flushing first so they show up
+    /// in the meantime
+    fn throttle(&mut self) -> Result<(), ChaosError> {
+        if let Some((rate, start)) = &mut self.rate {
+            let start = *start.get_or_insert_with(std::time::Instant::now);
+            let due = start + std::time::Duration::from_secs_f64(self.written as f64 / *rate);
+            let now = std::time::Instant::now();
+            if due > now {
+                self.flush()?;
+                std::thread::sleep(due - now);
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), ChaosError> {
+        for writer in &mut self.writers {
+            writer.flush().map_err(ChaosError::Output)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, writer: usize, line: &str) -> Result<(), ChaosError> {
+        writeln!(self.writers[writer], "{}", line).map_err(ChaosError::Output)
+    }
+}
+
+/// Spreads the bits of `value` out to every other bit, the low bit staying put
+fn spread_bits(value: u32) -> u64 {
+    let mut bits = u64::from(value);
+    bits = (bits | bits << 16) & 0x0000_ffff_0000_ffff;
+    bits = (bits | bits << 8) & 0x00ff_00ff_00ff_00ff;
+    bits = (bits | bits << 4) & 0x0f0f_0f0f_0f0f_0f0f;
+    bits = (bits | bits << 2) & 0x3333_3333_3333_3333;
+    (bits | bits << 1) & 0x5555_5555_5555_5555
+}
+
+/// The point's position along the Z-order curve through `bounds`: x and y quantized to 32 bits
+/// each across the box and interleaved, x in the even bits and y in the odd
+fn morton_code(bounds: &Bounds, point: &Point) -> u64 {
+    let quantize = |value: f64, min: f64, extent: f64| {
+        if extent > 0.0 {
+            ((value - min) / extent * f64::from(u32::MAX)).round() as u32
+        } else {
+            0
+        }
+    };
+    let x = quantize(point.x, bounds.min.x, bounds.width());
+    let y = quantize(point.y, bounds.min.y, bounds.height());
+    spread_bits(x) | spread_bits(y) << 1
+}
+
+/// Writes `value` rounded to `figures` significant figures, in plain decimal unless it's so large
+/// or small that the zeros would swamp the digits, in which case it's in scientific notation
+fn significant_figures(value: f64, figures: usize) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return format!("{}", value);
+    }
+    // Round in scientific notation first so the exponent accounts for rounding up, e.g. 9.99 to
+    // two figures is 1.0e1 and needs no decimals
+    let scientific = format!("{:.*e}", figures - 1, value);
+    let exponent: i32 = scientific
+        .rsplit('e')
+        .next()
+        .and_then(|exponent| exponent.parse().ok())
+        .expect("{:e} always writes an exponent");
+    if (-5..15).contains(&exponent) {
+        // Written from the rounded value so figures past the last one are zeros, 12345 to three
+        // figures being 12300
+        let rounded: f64 = scientific.parse().expect("{:e} writes a float");
+        let decimals = (figures as i32 - 1 - exponent).max(0) as usize;
+        format!("{:.*}", decimals, rounded)
+    } else {
+        scientific
+    }
+}
+
+/// Keeps a uniformly random sample of `capacity` items, lines or points, from a stream of unknown
+/// length using [reservoir sampling](https://en.wikipedia.org/wiki/Reservoir_sampling) (algorithm
+/// R). Memory is bounded by the capacity no matter how many items are offered.
+struct Reservoir<T> {
+    capacity: usize,
+    seen: usize,
+    items: Vec<(usize, T)>,
+    rng: StdRng,
+}
+
+impl<T> Reservoir<T> {
+    fn new(capacity: usize, rng: StdRng) -> Self {
+        Self {
+            capacity,
+            seen: 0,
+            items: Vec::with_capacity(capacity),
+            rng,
+        }
+    }
+
+    fn offer(&mut self, item: T) {
+        let index = self.seen;
+        self.seen += 1;
+        if self.items.len() < self.capacity {
+            self.items.push((index, item));
+        } else {
+            let slot = self.rng.gen_range(0, self.seen);
+            if slot < self.capacity {
+                self.items[slot] = (index, item);
+            }
+        }
+    }
+
+    /// The sampled items in the order they were offered
+    fn into_items(mut self) -> impl Iterator<Item = T> {
+        self.items.sort_by_key(|(index, _)| *index);
+        self.items.into_iter().map(|(_, item)| item)
+    }
+}
+
+/// The points generated so far for `--convergence`, bucketed into a grid over `bounds` sized for
+/// about two points per cell once all of them are in, so finding the nearest earlier point only
+/// searches the cells around it rather than every point.
+struct PriorPoints {
+    origin: Point,
+    size: f64,
+    cells: std::collections::HashMap<(i64, i64), Vec<Point>>,
+    /// The lowest and highest occupied columns and rows, which bound how far a search goes
+    min: (i64, i64),
+    max: (i64, i64),
+}
+
+impl PriorPoints {
+    fn new(bounds: Bounds, expected: usize) -> Self {
+        let side = bounds.width().max(bounds.height());
+        let per_side = (expected as f64 / 2.0).sqrt().ceil().max(1.0);
+        Self {
+            origin: bounds.min,
+            size: if side > 0.0 { side / per_side } else { 1.0 },
+            cells: std::collections::HashMap::new(),
+            min: (i64::MAX, i64::MAX),
+            max: (i64::MIN, i64::MIN),
+        }
+    }
+
+    fn cell_of(&self, point: &Point) -> (i64, i64) {
+        (
+            ((point.x - self.origin.x) / self.size).floor() as i64,
+            ((point.y - self.origin.y) / self.size).floor() as i64,
+        )
+    }
+
+    fn insert(&mut self, point: Point) {
+        let (column, row) = self.cell_of(&point);
+        self.min = (self.min.0.min(column), self.min.1.min(row));
+        self.max = (self.max.0.max(column), self.max.1.max(row));
+        self.cells.entry((column, row)).or_default().push(point);
+    }
+
+    /// The distance to the closest point inserted so far, infinite before the first
+    fn nearest(&self, point: &Point) -> f64 {
+        if self.cells.is_empty() {
+            return f64::INFINITY;
+        }
+        let (column, row) = self.cell_of(point);
+        let furthest = column
+            .saturating_sub(self.min.0)
+            .max(self.max.0.saturating_sub(column))
+            .max(row.saturating_sub(self.min.1))
+            .max(self.max.1.saturating_sub(row))
+            .max(0);
+        let mut nearest = f64::INFINITY;
+        // Anything in ring r + 1 is at least r cells away
+        for ring in 0..=furthest {
+            if nearest <= (ring - 1).max(0) as f64 * self.size {
+                break;
+            }
+            for dx in -ring..=ring {
+                for dy in -ring..=ring {
+                    if dx.abs() != ring && dy.abs() != ring {
+                        continue;
+                    }
+                    let cell = (column.saturating_add(dx), row.saturating_add(dy));
+                    for prior in self.cells.get(&cell).into_iter().flatten() {
+                        nearest = nearest.min(point.distance(prior));
+                    }
+                }
+            }
+        }
+        nearest
+    }
+}
+
+/// The distance from each point to the closest other point. The points are bucketed into a grid
+/// of about two points per cell, and each point searches rings of cells outwards from its own
+/// until no closer point could be in the next ring.
+fn nearest_neighbor_distances(points: &[Point]) -> Vec<f64> {
+    if points.len() < 2 {
+        return Vec::new();
+    }
+    let bounds = Bounds::of(points);
+    let side = bounds.width().max(bounds.height());
+    if side == 0.0 {
+        return vec![0.0; points.len()];
+    }
+    let cells = ((points.len() as f64 / 2.0).sqrt().ceil() as i64).max(1);
+    let size = side / cells as f64;
+    let cell_of = |point: &Point| {
+        (
+            ((point.x - bounds.min.x) / size) as i64,
+            ((point.y - bounds.min.y) / size) as i64,
+        )
+    };
+    let mut grid: std::collections::HashMap<(i64, i64), Vec<usize>> =
+        std::collections::HashMap::new();
+    for (i, point) in points.iter().enumerate() {
+        grid.entry(cell_of(point)).or_default().push(i);
+    }
+
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            let (column, row) = cell_of(point);
+            let mut nearest = f64::INFINITY;
+            // Anything in ring r + 1 is at least r cells away
+            for ring in 0..=cells {
+                if nearest <= (ring - 1).max(0) as f64 * size {
+                    break;
+                }
+                for dx in -ring..=ring {
+                    for dy in -ring..=ring {
+                        if dx.abs() != ring && dy.abs() != ring {
+                            continue;
+                        }
+                        for &j in grid.get(&(column + dx, row + dy)).into_iter().flatten() {
+                            if j != i {
+                                nearest = nearest.min(point.distance(&points[j]));
+                            }
+                        }
+                    }
+                }
+            }
+            nearest
+        })
+        .collect()
+}
+
+/// A one line summary of the nearest neighbor distances among `points` for `--nn-stats`
+fn nearest_neighbor_summary(points: &[Point]) -> String {
+    let mut distances = nearest_neighbor_distances(points);
+    if distances.is_empty() {
+        return "nearest neighbor: too few points to measure".to_string();
+    }
+    distances.sort_by(|a, b| a.partial_cmp(b).expect("Distances shouldn't be NaN"));
+    let quantile = |p: f64| distances[((distances.len() - 1) as f64 * p).round() as usize];
+    let mean = distances.iter().sum::<f64>() / distances.len() as f64;
+    format!(
+        "nearest neighbor over {} sampled points: min {} max {} mean {} median {} quartiles {} {}",
+        distances.len(),
+        distances[0],
+        distances[distances.len() - 1],
+        mean,
+        quantile(0.5),
+        quantile(0.25),
+        quantile(0.75)
+    )
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Options {
+    command: Option<String>,
+    /// Positional arguments after the command
+    arguments: Vec<String>,
+    quiet: bool,
+    sides: Option<usize>,
+    iterations: Option<usize>,
+    auto_iterations: bool,
+    trajectory: bool,
+    rotate_x: f64,
+    rotate_y: f64,
+    rotate_z: f64,
+    convergence: bool,
+    value_column: Option<ValueColumn>,
+    sample: Option<usize>,
+    output: Option<String>,
+    append: bool,
+    hull: bool,
+    with_hull: bool,
+    tile: Option<(usize, usize)>,
+    panels: Option<Vec<String>>,
+    cols: Option<usize>,
+    format: Format,
+    seed: Option<u64>,
+    orbit: Option<(f64, f64)>,
+    max_points: Option<usize>,
+    split: Option<usize>,
+    exact: bool,
+    gif: Option<String>,
+    frames: usize,
+    config: Option<String>,
+    svg_input: Option<String>,
+    invert_y: bool,
+    dedup_epsilon: Option<f64>,
+    color: Option<ColorScheme>,
+    color_palette: Palette,
+    quantize_colors: Option<usize>,
+    svg: Option<String>,
+    svg_radius: Option<f64>,
+    svg_color: String,
+    svg_background: String,
+    stats: bool,
+    jump_distance: Option<Vec<Fraction>>,
+    weighted_jumps: Option<Vec<WeightedJump>>,
+    threads: Option<usize>,
+    estimate_memory: bool,
+    rule: Option<subdivision::Rule>,
+    depth: usize,
+    vertices_file: Option<String>,
+    medial_depth: Option<usize>,
+    open: bool,
+    start_weights: Option<Vec<f64>>,
+    resume_from: Option<String>,
+    non_finite: NonFinite,
+    target_set: Option<TargetSet>,
+    every: usize,
+    decimate_adaptive: Option<usize>,
+    grid_unique: Option<f64>,
+    c: Option<(f64, f64)>,
+    a: Option<f64>,
+    b: Option<f64>,
+    clip_to_polygon: bool,
+    summary_only: bool,
+    scale: Option<f64>,
+    integer: bool,
+    rotate_output: Option<f64>,
+    variant: Option<Fern>,
+    emit_selection: Option<String>,
+    selection_histogram: bool,
+    rows: Option<usize>,
+    contains: Option<Point>,
+    mirror: Option<Axis>,
+    flush_every: Option<usize>,
+    points_per_line: Option<usize>,
+    k: Option<f64>,
+    points: Option<usize>,
+    sig_figs: Option<usize>,
+    delta: bool,
+    print_command: bool,
+    allow_extrapolation: bool,
+    meta: Option<String>,
+    z_order: bool,
+    histogram: Option<usize>,
+    histogram_binary: bool,
+    verify: bool,
+    affine: Option<AffineTransform>,
+    order: Option<u32>,
+    theta_offset: Option<f64>,
+    nn_stats: bool,
+    until_coverage: Option<f64>,
+    depth_shade: bool,
+    restrict_expr: Option<restriction::Expr>,
+    rate: Option<f64>,
+    /// The fractal's holes with `--verify`, filled in from `Fractal::holes` rather than a flag
+    holes: Vec<Vec<Point>>,
+    cell_count_check: bool,
+    /// How the fractal is made of copies with `--cell-count-check`, from `Fractal::self_similarity`
+    self_similarity: Option<SelfSimilarity>,
+    /// The fractal's own rule for picking targets with `--meta`, from `Fractal::restriction`
+    restriction: Option<&'static str>,
+}
+
+impl Options {
+    /// Parses the arguments following the binary name. The first positional argument is the
+    /// fractal to generate, everything starting with `--` is a flag.
+    fn parse<I>(args: I) -> Result<Self, ChaosError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut options = Options {
+            frames: FRAMES,
+            depth: DEPTH,
+            every: 1,
+            svg_color: SVG_COLOR.to_string(),
+            svg_background: SVG_BACKGROUND.to_string(),
+            ..Options::default()
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--quiet" => options.quiet = true,
+                "--sides" => options.sides = Some(parse_value(&arg, args.next())?),
+                "--iterations" => options.iterations = Some(parse_value(&arg, args.next())?),
+                "--auto-iterations" => options.auto_iterations = true,
+                "--trajectory" => options.trajectory = true,
+                "--rotate-x" => options.rotate_x = parse_value(&arg, args.next())?,
+                "--rotate-y" => options.rotate_y = parse_value(&arg, args.next())?,
+                "--rotate-z" => options.rotate_z = parse_value(&arg, args.next())?,
+                "--convergence" => options.convergence = true,
+                "--color" => options.color = Some(parse_value(&arg, args.next())?),
+                "--color-palette" => options.color_palette = parse_value(&arg, args.next())?,
+                "--quantize-colors" => {
+                    options.quantize_colors = Some(parse_value(&arg, args.next())?)
+                }
+                "--value-column" => options.value_column = Some(parse_value(&arg, args.next())?),
+                "--sample" => options.sample = Some(parse_value(&arg, args.next())?),
+                "--output" | "--out" => options.output = Some(parse_value(&arg, args.next())?),
+                "--append" => options.append = true,
+                "--hull" => options.hull = true,
+                "--with-hull" => options.with_hull = true,
+                "--z-order" => options.z_order = true,
+                "--histogram" => options.histogram = Some(parse_value(&arg, args.next())?),
+                "--histogram-binary" => options.histogram_binary = true,
+                "--nn-stats" => options.nn_stats = true,
+                "--depth-shade" => options.depth_shade = true,
+                "--restrict-expr" => options.restrict_expr = Some(parse_value(&arg, args.next())?),
+                "--until-coverage" => {
+                    let fraction: f64 = parse_value(&arg, args.next())?;
+                    if !(fraction > 0.0 && fraction <= 1.0) {
+                        return Err(ChaosError::Invalid(format!(
+                            "{} takes a fraction of the grid more than 0 and at most 1",
+                            arg
+                        )));
+                    }
+                    options.until_coverage = Some(fraction);
+                }
+                "--verify" => options.verify = true,
+                "--cell-count-check" => options.cell_count_check = true,
+                "--format" => options.format = parse_value(&arg, args.next())?,
+                "--seed" => options.seed = Some(parse_value(&arg, args.next())?),
+                "--max-points" => options.max_points = Some(parse_value(&arg, args.next())?),
+                "--split" => options.split = Some(parse_value(&arg, args.next())?),
+                "--exact" => options.exact = true,
+                "--gif" => options.gif = Some(parse_value(&arg, args.next())?),
+                "--frames" => options.frames = parse_value(&arg, args.next())?,
+                "--rows" => options.rows = Some(parse_value(&arg, args.next())?),
+                "--emit-selection" => {
+                    options.emit_selection = Some(parse_value(&arg, args.next())?)
+                }
+                "--selection-histogram" => options.selection_histogram = true,
+                "--variant" => options.variant = Some(parse_value(&arg, args.next())?),
+                "--config" => options.config = Some(parse_value(&arg, args.next())?),
+                "--svg-input" => options.svg_input = Some(parse_value(&arg, args.next())?),
+                "--invert-y" => options.invert_y = true,
+                "--stats" => options.stats = true,
+                "--summary-only" => options.summary_only = true,
+                "--estimate-memory" => options.estimate_memory = true,
+                "--rule" => options.rule = Some(parse_value(&arg, args.next())?),
+                "--depth" => options.depth = parse_value(&arg, args.next())?,
+                "--vertices-file" => options.vertices_file = Some(parse_value(&arg, args.next())?),
+                "--medial-depth" => options.medial_depth = Some(parse_value(&arg, args.next())?),
+                "--open" => options.open = true,
+                "--clip-to-polygon" => options.clip_to_polygon = true,
+                "--rotate-output" => options.rotate_output = Some(parse_value(&arg, args.next())?),
+                "--scale" => options.scale = Some(parse_value(&arg, args.next())?),
+                "--integer" => options.integer = true,
+                "--allow-extrapolation" => options.allow_extrapolation = true,
+                "--sig-figs" => options.sig_figs = Some(parse_value(&arg, args.next())?),
+                "--delta" => options.delta = true,
+                "--print-command" => options.print_command = true,
+                "--every" => options.every = parse_value(&arg, args.next())?,
+                "--grid-unique" => options.grid_unique = Some(parse_value(&arg, args.next())?),
+                "--decimate-adaptive" => {
+                    options.decimate_adaptive = Some(parse_value(&arg, args.next())?)
+                }
+                "--target-set" => options.target_set = Some(parse_value(&arg, args.next())?),
+                "--non-finite" => options.non_finite = parse_value(&arg, args.next())?,
+                "--start-weights" => options.start_weights = Some(parse_list(&arg, args.next())?),
+                "--resume-from" => options.resume_from = Some(parse_value(&arg, args.next())?),
+                "--threads" => options.threads = Some(parse_value(&arg, args.next())?),
+                "--jump-distance" => options.jump_distance = Some(parse_list(&arg, args.next())?),
+                "--jump-distances" => options.weighted_jumps = Some(parse_list(&arg, args.next())?),
+                "--svg" => options.svg = Some(parse_value(&arg, args.next())?),
+                "--svg-radius" => options.svg_radius = Some(parse_value(&arg, args.next())?),
+                "--svg-color" => options.svg_color = parse_value(&arg, args.next())?,
+                "--svg-background" => options.svg_background = parse_value(&arg, args.next())?,
+                "--dedup-epsilon" => options.dedup_epsilon = Some(parse_value(&arg, args.next())?),
+                "--c" => match parse_list(&arg, args.next())?[..] {
+                    [re, im] => options.c = Some((re, im)),
+                    _ => return Err(ChaosError::Invalid(format!("{} takes re,im", arg))),
+                },
+                "--a" => options.a = Some(parse_value(&arg, args.next())?),
+                "--b" => options.b = Some(parse_value(&arg, args.next())?),
+                "--theta-offset" => options.theta_offset = Some(parse_value(&arg, args.next())?),
+                "--order" => options.order = Some(parse_value(&arg, args.next())?),
+                "--k" => options.k = Some(parse_value(&arg, args.next())?),
+                "--points" => options.points = Some(parse_value(&arg, args.next())?),
+                "--meta" => options.meta = Some(parse_value(&arg, args.next())?),
+                "--rate" => options.rate = Some(parse_value(&arg, args.next())?),
+                "--flush-every" => options.flush_every = Some(parse_value(&arg, args.next())?),
+                "--points-per-line" => {
+                    options.points_per_line = Some(parse_value(&arg, args.next())?)
+                }
+                "--mirror" => options.mirror = Some(parse_value(&arg, args.next())?),
+                "--affine" => match parse_list(&arg, args.next())?[..] {
+                    [a, b, c, d, e, f] => {
+                        options.affine = Some(AffineTransform::new(a, b, c, d, e, f))
+                    }
+                    ref values => {
+                        return Err(ChaosError::Invalid(format!(
+                            "{} takes six numbers a,b,c,d,e,f but got {}",
+                            arg,
+                            values.len()
+                        )))
+                    }
+                },
+                "--contains" => match parse_list(&arg, args.next())?[..] {
+                    [x, y] => options.contains = Some(Point::new(x, y)),
+                    _ => return Err(ChaosError::Invalid(format!("{} takes x,y", arg))),
+                },
+                "--orbit" => match parse_list(&arg, args.next())?[..] {
+                    [radius, speed] => options.orbit = Some((radius, speed)),
+                    _ => return Err(ChaosError::Invalid(format!("{} takes radius,speed", arg))),
+                },
+                "--tile" => {
+                    let rows = parse_value(&arg, args.next())?;
+                    let columns = parse_value(&arg, args.next())?;
+                    options.tile = Some((rows, columns));
+                }
+                "--panels" => options.panels = Some(parse_list(&arg, args.next())?),
+                "--cols" => options.cols = Some(parse_value(&arg, args.next())?),
+                flag if flag.starts_with("--") => {
+                    return Err(ChaosError::Invalid(format!(
+                        "{} is not a recognized flag",
+                        flag
+                    )))
+                }
+                _ if options.command.is_none() => options.command = Some(arg),
+                _ => options.arguments.push(arg),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Fills in options that weren't given as flags from the environment, looked up through `var`.
+    /// Flags win over the environment, which wins over the built in defaults.
+    fn with_env<F>(mut self, var: F) -> Result<Self, ChaosError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if self.iterations.is_none() {
+            self.iterations = var("CHAOS_ITERATIONS")
+                .map(|value| parse_value("CHAOS_ITERATIONS", Some(value)))
+                .transpose()?;
+        }
+        if self.jump_distance.is_none() && self.weighted_jumps.is_none() {
+            self.jump_distance = var("CHAOS_JUMP_DISTANCE")
+                .map(|value| parse_list("CHAOS_JUMP_DISTANCE", Some(value)))
+                .transpose()?;
+        }
+        Ok(self)
+    }
+
+    /// Checks the flags make sense together before anything runs, so a bad combination is an
+    /// error up front rather than partway through writing the output
+    fn check(&self) -> Result<(), ChaosError> {
+        let invalid = |message: &str| Err(ChaosError::Invalid(message.to_string()));
+        for jump in self.jump_distance.iter().flatten() {
+            jump.jump_distance(self.allow_extrapolation)
+                .map_err(|err| ChaosError::Invalid(format!("--jump-distance {}", err)))?;
+        }
+        for jump in self.weighted_jumps.iter().flatten() {
+            jump.distance
+                .jump_distance(self.allow_extrapolation)
+                .map_err(|err| ChaosError::Invalid(format!("--jump-distances {}", err)))?;
+            if !(jump.weight > 0.0 && jump.weight.is_finite()) {
+                return Err(ChaosError::Invalid(format!(
+                    "--jump-distances weight {} is not a positive number",
+                    jump.weight
+                )));
+            }
+        }
+        if self.jump_distance.is_some() && self.weighted_jumps.is_some() {
+            return invalid("--jump-distance and --jump-distances can't be combined");
+        }
+        if cfg!(not(feature = "exact")) && self.exact {
+            return invalid("--exact needs a build with --features exact");
+        }
+        if cfg!(not(feature = "gif")) && self.gif.is_some() {
+            return invalid("--gif needs a build with --features gif");
+        }
+        if self.split == Some(0) {
+            return invalid("--split needs at least 1 file");
+        }
+        if self.every == 0 {
+            return invalid("--every needs at least 1, every point");
+        }
+        if self
+            .grid_unique
+            .is_some_and(|resolution| !(resolution > 0.0 && resolution.is_finite()))
+        {
+            return invalid("--grid-unique needs a cell size more than 0");
+        }
+        if self
+            .tile
+            .is_some_and(|(rows, columns)| rows == 0 || columns == 0)
+        {
+            return invalid("--tile needs at least 1 row and 1 column");
+        }
+        if self.histogram == Some(0) {
+            return invalid("--histogram needs at least 1 cell across");
+        }
+        if self.sig_figs == Some(0) {
+            return invalid("--sig-figs needs at least 1 figure");
+        }
+        if self.points_per_line == Some(0) {
+            return invalid("--points-per-line needs at least 1 point");
+        }
+        if self.flush_every == Some(0) {
+            return invalid("--flush-every needs at least 1 row");
+        }
+        if self
+            .rate
+            .is_some_and(|rate| !(rate > 0.0 && rate.is_finite()))
+        {
+            return invalid("--rate needs a number of rows a second more than 0");
+        }
+        if self.quantize_colors == Some(0) {
+            return invalid("--quantize-colors needs at least 1 color");
+        }
+        if self.frames == 0 {
+            return invalid("--frames needs at least 1 frame");
+        }
+        match (&self.output, self.split) {
+            (Some(base), Some(_)) if connect_address(base).is_some() => {
+                return invalid("--split writes to files, it can't split a socket");
+            }
+            (None, Some(_)) => return invalid("--split needs --output for the base file name"),
+            _ => {}
+        }
+        if self.delta && (self.sample.is_some() || self.split.is_some()) {
+            return invalid(
+                "--delta can't be combined with --sample or --split, the rows wouldn't follow on",
+            );
+        }
+        if self.points_per_line.is_some() && self.split.is_some() {
+            return invalid("--points-per-line can't be combined with --split");
+        }
+        if self.with_hull
+            && (self.hull
+                || self.z_order
+                || self.summary_only
+                || self.delta
+                || self.color.is_some()
+                || self.value_column.is_some()
+                || self.tile.is_some()
+                || self.mirror.is_some()
+                || self.sample.is_some()
+                || self.split.is_some()
+                || self.histogram.is_some()
+                || self.svg.is_some()
+                || self.gif.is_some())
+        {
+            return invalid(
+                "--with-hull can't be combined with --hull, --z-order, --summary-only, --delta, \
+                 --color, --value-column, --tile, --mirror, --sample, --split, --histogram, \
+                 --svg, or --gif",
+            );
+        }
+        if self.threads.is_some_and(|threads| threads > 1)
+            && (self.hull
+                || self.with_hull
+                || self.convergence
+                || self.stats
+                || self.nn_stats
+                || self.selection_histogram
+                || self.auto_iterations
+                || self.summary_only
+                || self.z_order
+                || self.gif.is_some()
+                || self.svg.is_some()
+                || self.histogram.is_some()
+                || self.delta
+                || self.until_coverage.is_some()
+                || self.decimate_adaptive.is_some()
+                || self.cell_count_check
+                || self.verify
+                || self.emit_selection.is_some()
+                || self.meta.is_some())
+        {
+            return invalid(
+                "--threads can't be combined with --hull, --with-hull, --convergence, --stats, \
+                 --nn-stats, --selection-histogram, --auto-iterations, --summary-only, --gif, \
+                 --svg, --z-order, --histogram, --delta, --until-coverage, \
+                 --decimate-adaptive, --cell-count-check, --verify, --emit-selection, or --meta",
+            );
+        }
+        if self.panels.is_some()
+            && (self.hull
+                || self.with_hull
+                || self.convergence
+                || self.summary_only
+                || self.z_order
+                || self.threads.is_some_and(|threads| threads > 1)
+                || self.gif.is_some()
+                || self.svg.is_some()
+                || self.histogram.is_some()
+                || self.estimate_memory)
+        {
+            return invalid(
+                "--panels can't be combined with --hull, --with-hull, --convergence, \
+                 --summary-only, --gif, --svg, --z-order, --histogram, --threads, or \
+                 --estimate-memory",
+            );
+        }
+        if let (Some(_), Some(command)) = (&self.panels, &self.command) {
+            return Err(ChaosError::Invalid(format!(
+                "--panels names the fractals to play, {} can't be given as well",
+                command
+            )));
+        }
+        Ok(())
+    }
+
+    /// The distances `--jump-distances` draws from or `--jump-distance` cycles through, or just
+    /// `default`, the fractal's own
+    fn jump_distances(&self, default: f64) -> JumpDistances {
+        if let Some(jumps) = &self.weighted_jumps {
+            let weights = WeightedIndex::new(jumps.iter().map(|jump| jump.weight))
+                .unwrap_or_else(|err| panic!("--jump-distances: {}", err));
+            return JumpDistances::Weighted(
+                jumps.iter().map(|jump| jump.distance.0).collect(),
+                weights,
+            );
+        }
+        match &self.jump_distance {
+            Some(jumps) => JumpDistances::Cycle(jumps.iter().map(|jump| jump.0).collect()),
+            None => JumpDistances::Cycle(vec![default]),
+        }
+    }
+
+    /// The first flag given that only the polygon fractals understand, to turn down elsewhere
+    fn polygon_flag(&self) -> Option<&'static str> {
+        [
+            (self.contains.is_some(), "--contains"),
+            (self.clip_to_polygon, "--clip-to-polygon"),
+            (self.start_weights.is_some(), "--start-weights"),
+        ]
+        .iter()
+        .find(|(given, _)| *given)
+        .map(|(_, flag)| *flag)
+    }
+
+    /// `--theta-offset` in radians, 0 without it
+    fn theta_offset(&self) -> f64 {
+        self.theta_offset.unwrap_or(0.0).to_radians()
+    }
+
+    /// `--iterations`, or the default. With `--until-coverage` it's only a limit, and a higher one.
+    fn iterations(&self) -> usize {
+        match self.until_coverage {
+            Some(_) => self.iterations.unwrap_or(COVERAGE_ITERATIONS),
+            None => self.iterations.unwrap_or(ITERATIONS),
+        }
+    }
+
+    /// Fills in a seed from the OS's entropy source when `--seed` wasn't given and logs it, so the
+    /// run is as random as an unseeded one and can still be replayed
+    fn with_logged_seed(self) -> Self {
+        if self.seed.is_some() {
+            return self;
+        }
+        let seed = rand::rngs::OsRng.gen();
+        diagnostic!("seed: {}", seed);
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// A fresh generator for the run. Seeded by `--seed` when given so every call replays the same
+    /// numbers, otherwise seeded from the OS.
+    fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+}
+
+/// Parses the value following `flag`, with an error naming the flag if it's missing or malformed
+fn parse_value<T>(flag: &str, value: Option<String>) -> Result<T, ChaosError>
+where
+    T: std::str::FromStr,
+{
+    let value = value.ok_or_else(|| ChaosError::Invalid(format!("{} requires a value", flag)))?;
+    value
+        .parse()
+        .map_err(|_| ChaosError::Parse(format!("{} is not a valid value for {}", value, flag)))
+}
+
+/// Parses the comma separated values following `flag`, like `parse_value` does for one
+fn parse_list<T>(flag: &str, value: Option<String>) -> Result<Vec<T>, ChaosError>
+where
+    T: std::str::FromStr,
+{
+    let value: String = parse_value(flag, value)?;
+    value
+        .split(',')
+        .map(|item| parse_value(flag, Some(item.trim().to_string())))
+        .collect()
+}
+
+/// Reads the point rows of a file written by `chaos-game`, skipping blank and `#` lines. Each
+/// point comes with its whole row so any extra columns can be passed along.
+fn parse_points(text: &str) -> Result<Vec<(Point, &str)>, String> {
+    let mut points = Vec::new();
+    for (number, line) in text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+    {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut values = line.split_whitespace().map(|value| value.parse::<f64>());
+        match (values.next(), values.next()) {
+            (Some(Ok(x)), Some(Ok(y))) => points.push((Point::new(x, y), line)),
+            _ => return Err(format!("line {}: expected x y, got {}", number, line)),
+        }
+    }
+    Ok(points)
+}
+
+/// The point on the last line of a point file, read from the end so a long run isn't read in full
+fn last_point(path: &str) -> Result<Point, ChaosError> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    // Far longer than any row, even one with every extra column
+    const TAIL_BYTES: u64 = 4096;
+    let read = || -> Result<Point, ChaosError> {
+        let mut file = std::fs::File::open(path)?;
+        let length = file.metadata()?.len();
+        file.seek(SeekFrom::Start(length.saturating_sub(TAIL_BYTES)))?;
+        let mut tail = Vec::new();
+        file.read_to_end(&mut tail)?;
+
+        let tail = String::from_utf8_lossy(&tail);
+        let line = tail
+            .lines()
+            .map(str::trim)
+            .rfind(|line| !line.is_empty() && !line.starts_with('#'))
+            .ok_or_else(|| ChaosError::Invalid("there's no point to resume from".to_string()))?;
+        match parse_points(line).map_err(ChaosError::Parse)?[..] {
+            [(point, _)] => Ok(point),
+            _ => unreachable!("The line isn't empty or a comment"),
+        }
+    };
+    read().map_err(ChaosError::in_file(path))
+}
+
+/// The indices of the points to keep so none is within `epsilon` of an earlier kept one. Points
+/// are bucketed into a grid of `epsilon` sized cells so only neighboring cells are compared.
+fn dedup_points(points: &[Point], epsilon: f64) -> Vec<usize> {
+    if epsilon <= 0.0 {
+        // Adding 0 turns -0 into 0, which is the same point but not the same bits
+        let bits = |value: f64| (value + 0.0).to_bits();
+        let mut seen = std::collections::HashSet::new();
+        return (0..points.len())
+            .filter(|&i| seen.insert((bits(points[i].x), bits(points[i].y))))
+            .collect();
+    }
+
+    let cell = |point: &Point| {
+        (
+            (point.x / epsilon).floor() as i64,
+            (point.y / epsilon).floor() as i64,
+        )
+    };
+    let mut grid: std::collections::HashMap<(i64, i64), Vec<usize>> =
+        std::collections::HashMap::new();
+    let mut kept = Vec::new();
+    for (i, point) in points.iter().enumerate() {
+        let (column, row) = cell(point);
+        let duplicate = (-1..=1).any(|dx| {
+            (-1..=1).any(|dy| {
+                grid.get(&(column + dx, row + dy)).is_some_and(|indices| {
+                    indices
+                        .iter()
+                        .any(|&j| points[j].distance_squared(point) <= epsilon * epsilon)
+                })
+            })
+        });
+        if !duplicate {
+            grid.entry((column, row)).or_default().push(i);
+            kept.push(i);
+        }
+    }
+    kept
+}
+
+/// Roughly how many bytes a value takes in a row, `Display` prints most coordinates with 15 to 17
+/// significant digits
+const BYTES_PER_VALUE: usize = 18;
+
+/// What `--estimate-memory` expects a run to take, worked out from the options alone
+#[derive(Debug, Clone, PartialEq)]
+struct Estimate {
+    rows: usize,
+    output_bytes: usize,
+    memory_bytes: usize,
+}
+
+impl Estimate {
+    fn of(options: &Options) -> Self {
+        let iterations = options.iterations();
+        let copies = options.tile.map_or(1, |(rows, columns)| rows * columns);
+        let points = iterations.div_ceil(options.every.max(1)) * copies;
+        let columns = if options.convergence {
+            2
+        } else {
+            2 + usize::from(options.value_column.is_some())
+                + usize::from(options.color.is_some())
+                + usize::from(options.tile.is_some())
+                + usize::from(options.with_hull)
+        };
+        // Each value is followed by a space or the newline
+        let row_bytes = columns * (BYTES_PER_VALUE + 1);
+
+        let rows = if options.hull {
+            // A handful of vertices however many points there are
+            0
+        } else if let Some(cells) = options.histogram {
+            cells * cells
+        } else {
+            let rows = if options.convergence {
+                iterations.saturating_sub(1)
+            } else {
+                points
+            };
+            rows.min(options.sample.unwrap_or(rows))
+                .min(options.max_points.unwrap_or(rows))
+        };
+
+        let point_bytes = std::mem::size_of::<Point>();
+        let memory_bytes = if options.hull || options.with_hull {
+            points * point_bytes
+        } else if let Some(cells) = options.histogram {
+            cells * cells * std::mem::size_of::<u64>()
+        } else if options.z_order {
+            // Each point, its extra columns, and the vector holding them
+            let columns = columns - 2;
+            points * (point_bytes + columns * 8 + std::mem::size_of::<Vec<f64>>())
+        } else if options.convergence {
+            iterations * point_bytes
+        } else if let Some(sample) = options.sample {
+            sample.min(points) * row_bytes
+        } else if let Some(threads) = options.threads {
+            // One chain's rows are read back at a time
+            points.div_ceil(threads.max(1)) * row_bytes
+        } else {
+            0
+        };
+
+        Self {
+            rows,
+            output_bytes: rows * row_bytes,
+            memory_bytes,
+        }
+    }
+}
+
+/// `bytes` in the largest unit that keeps it above 1, e.g. `1.5 MB`
+fn human_bytes(bytes: usize) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit + 1 < units.len() {
+        size /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+/// A fractal that can be generated from the command line. Implement this and add it to
+/// `fractals` to make a new fractal available to `main`, `list`, and `describe`. It's shared
+/// between threads with `--threads`.
+trait Fractal: Sync {
+    /// The subcommand that generates it
+    fn name(&self) -> &'static str;
+
+    /// A sentence or two on what the fractal is and how it's generated
+    fn describe(&self) -> &'static str;
+
+    /// Generates the fractal, printing its points to stdout
+    fn run(&self, options: &Options) -> Result<(), ChaosError>;
+
+    /// Polygons the fractal should leave empty, like the middle of the Sierpinski triangle.
+    /// `--verify` checks no points land in them. None by default.
+    fn holes(&self) -> Vec<Vec<Point>> {
+        Vec::new()
+    }
+
+    /// How the fractal is made of copies of itself, for `--cell-count-check`. None by default,
+    /// for fractals that aren't exactly self-similar or whose copies depend on the options.
+    fn self_similarity(&self) -> Option<SelfSimilarity> {
+        None
+    }
+
+    /// The rule the fractal picks its targets by, as a `--restrict-expr` expression, for `--meta`.
+    /// None by default, for a uniform pick.
+    fn restriction(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether the fractal plays the chaos game on a polygon, so flags about its vertices and
+    /// edges like `--start-weights` apply. False by default.
+    fn on_polygon(&self) -> bool {
+        false
+    }
+}
+
+/// How a fractal is made of smaller copies of itself: `copies` of them, each `ratio` times the
+/// size of the whole
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct SelfSimilarity {
+    copies: usize,
+    ratio: f64,
+}
+
+impl SelfSimilarity {
+    /// The cells along each side of a grid over the whole with cells the size of the copies
+    /// `depth` levels down, saturating rather than overflowing for deep grids
+    fn side(&self, depth: usize) -> usize {
+        (1.0 / self.ratio).powf(depth as f64).round().max(1.0) as usize
+    }
+
+    /// The copies `depth` levels down, as a float since it outgrows a `usize` well before `side`
+    fn expected(&self, depth: usize) -> f64 {
+        (self.copies as f64).powf(depth as f64)
+    }
+}
+
+/// A fractal generated by a plain function, which covers all of the built in ones
+struct Preset {
+    name: &'static str,
+    description: &'static str,
+    generate: fn(&Options) -> Result<(), ChaosError>,
+    holes: Vec<Vec<Point>>,
+    self_similarity: Option<SelfSimilarity>,
+    restriction: Option<&'static str>,
+    polygon: bool,
+}
+
+impl Preset {
+    fn new(
+        name: &'static str,
+        description: &'static str,
+        generate: fn(&Options) -> Result<(), ChaosError>,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            generate,
+            holes: Vec::new(),
+            self_similarity: None,
+            restriction: None,
+            polygon: false,
+        }
+    }
+
+    /// Declares the polygons the fractal leaves empty for `--verify`
+    fn with_holes(mut self, holes: Vec<Vec<Point>>) -> Self {
+        self.holes = holes;
+        self
+    }
+
+    /// Declares the fractal is `copies` copies of itself each `ratio` times the size, for
+    /// `--cell-count-check`
+    fn with_self_similarity(mut self, copies: usize, ratio: f64) -> Self {
+        self.self_similarity = Some(SelfSimilarity { copies, ratio });
+        self
+    }
+
+    /// Declares the rule the fractal picks its targets by, for `--meta`
+    fn with_restriction(mut self, expr: &'static str) -> Self {
+        self.restriction = Some(expr);
+        self
+    }
+
+    /// Declares the fractal plays on a polygon, through `Polygon::chaos_game`
+    fn on_polygon(mut self) -> Self {
+        self.polygon = true;
+        self
+    }
+}
+
+impl Fractal for Preset {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn describe(&self) -> &'static str {
+        self.description
+    }
+
+    fn run(&self, options: &Options) -> Result<(), ChaosError> {
+        (self.generate)(options)
+    }
+
+    fn holes(&self) -> Vec<Vec<Point>> {
+        self.holes.clone()
+    }
+
+    fn self_similarity(&self) -> Option<SelfSimilarity> {
+        self.self_similarity
+    }
+
+    fn restriction(&self) -> Option<&'static str> {
+        self.restriction
+    }
+
+    fn on_polygon(&self) -> bool {
+        self.polygon
+    }
+}
+
+/// Every fractal the binary knows about, in the order `list` prints them
+fn fractals() -> Vec<Box<dyn Fractal>> {
+    vec![
+        Box::new(Preset::new(
+            "sierpinski-triangle",
+            "Jumps halfway towards a random vertex of an equilateral triangle.",
+            sierpinski_triangle,
+        )
+        .on_polygon()
+        .with_holes(vec![Triangle::new_equilateral(100.0).medial_points()])
+        .with_self_similarity(3, 0.5)),
+        Box::new(Preset::new(
+            "sierpinski-triangle-no-repeat",
+            "Jumps halfway towards a random vertex of an equilateral triangle, never picking the same vertex twice in a row.",
+            sierpinski_triangle_no_repeat,
+        )
+        .on_polygon()
+        .with_restriction("c != p")),
+        Box::new(Preset::new(
+            "square-one",
+            "Jumps halfway towards a random vertex of a square, never picking the same vertex twice in a row.",
+            square_one,
+        )
+        .on_polygon()
+        .with_restriction("c != p")),
+        Box::new(Preset::new(
+            "square-two",
+            "Jumps halfway towards a random vertex of a square that shares an x or y coordinate with the previous one.",
+            square_two,
+        )
+        .on_polygon()
+        .with_restriction("(c - p) % n != 2")),
+        Box::new(Preset::new(
+            "vicsek",
+            "Jumps two thirds of the way towards a random corner or the center of a square.",
+            vicsek_fractal,
+        )
+        .on_polygon()
+        .with_holes(vicsek_holes())
+        .with_self_similarity(5, 1.0 / 3.0)),
+        Box::new(Preset::new(
+            "nflake",
+            "Jumps towards a random vertex of a regular polygon with --sides vertices, scaled so the copies just touch.",
+            nflake,
+        )
+        .on_polygon()),
+        Box::new(Preset::new(
+            "sierpinski-octagon",
+            "Jumps towards a random vertex of a regular octagon, scaled by 1/(2 + sqrt(2)) so the eight copies just touch.",
+            sierpinski_octagon,
+        )
+        .on_polygon()),
+        Box::new(Preset::new(
+            "triangle-in-square",
+            "Jumps halfway towards a random vertex of a square or of a triangle inside it.",
+            triangle_in_square,
+        )
+        .on_polygon()),
+        Box::new(Preset::new(
+            "hexaflake",
+            "Jumps two thirds of the way towards a random vertex or the center of a regular hexagon.",
+            hexaflake,
+        )
+        .on_polygon()
+        .with_self_similarity(7, 1.0 / 3.0)),
+        Box::new(Preset::new(
+            "heighway-dragon",
+            "Jumps towards one of two points, rotating around it by 45 or 135 degrees after each jump.",
+            heighway_dragon,
+        )),
+        Box::new(Preset::new(
+            "sierpinski-tetrahedron",
+            "Jumps halfway towards a random vertex of a regular tetrahedron, printing x y z rows for splot.",
+            sierpinski_tetrahedron,
+        )),
+        Box::new(Preset::new(
+            "julia",
+            "Runs z -> z^2 + c backwards, taking a random square root each time, to land on the Julia set of --c.",
+            julia,
+        )),
+        Box::new(Preset::new(
+            "ellipse-attractor",
+            "Jumps halfway towards a random point on the ellipse with semi-axes --a and --b, filling it with a fuzzy cloud.",
+            ellipse_attractor,
+        )),
+        Box::new(Preset::new(
+            "svg-polygon",
+            "Jumps halfway towards a random vertex of the polygon or straight-line path read from --svg-input.",
+            svg_polygon,
+        )
+        .on_polygon()),
+        Box::new(Preset::new(
+            "barnsley-fern",
+            "Maps the point with one of four affine transforms picked by weight, drawing the fern species chosen with --variant.",
+            barnsley_fern,
+        )),
+        Box::new(Preset::new(
+            "ifs",
+            "Applies an affine transform picked by weight to the point, the Barnsley fern unless --config lists others.",
+            ifs,
+        )),
+    ]
+}
+
+/// Generated when no command is given
+const DEFAULT_FRACTAL: &str = "sierpinski-triangle";
+
+/// Has Ctrl-C set `INTERRUPTED` instead of killing the process, so buffered points aren't lost.
+/// A second Ctrl-C exits straight away in case the first isn't being noticed.
+#[cfg(unix)]
+fn handle_interrupts() {
+    extern "C" fn interrupted(_: libc::c_int) {
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            unsafe { libc::_exit(INTERRUPTED_STATUS) }
+        }
+    }
+    let handler = interrupted as extern "C" fn(libc::c_int);
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+}
+
+#[cfg(not(unix))]
+fn handle_interrupts() {}
+
+fn main() {
+    handle_interrupts();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => {}
+        Err(ChaosError::Outside) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    }
+    if INTERRUPTED.load(Ordering::Relaxed) {
+        std::process::exit(INTERRUPTED_STATUS);
+    }
+}
+
+/// Parses and checks `args` and runs the command they ask for
+fn run(args: &[String]) -> Result<(), ChaosError> {
+    let options = Options::parse(args.to_vec())?.with_env(|name| std::env::var(name).ok())?;
+    QUIET.store(options.quiet, Ordering::Relaxed);
+    options.check()?;
+    let fractals = fractals();
+    // Subcommands don't play on a polygon either
+    if let Some(name) = options.command.as_deref() {
+        if find_fractal(&fractals, name).is_none() {
+            check_polygon_flags(&options, name, false)?;
+        }
+    }
+
+    match options.command.as_deref() {
+        Some("list") => {
+            for fractal in &fractals {
+                println!("{}", fractal.name());
+            }
+            Ok(())
+        }
+        Some("describe") => {
+            for name in &options.arguments {
+                let fractal = find_fractal_or_error(&fractals, name)?;
+                println!("{}: {}", fractal.name(), fractal.describe());
+            }
+            Ok(())
+        }
+        Some("merge") => merge(&options),
+        Some("subdivide") => subdivide(&options),
+        Some("pascal") => pascal(&options),
+        Some("bench") => bench(&options),
+        Some("rose") => rose(&options),
+        Some("apollonian") => apollonian(&options),
+        Some("hilbert") => hilbert(&options),
+        Some("penrose") => penrose(&options),
+        Some("medial") => medial(&options),
+        command => {
+            if let Some(extra) = options.arguments.first() {
+                return Err(ChaosError::Invalid(format!(
+                    "unexpected argument {}",
+                    extra
+                )));
+            }
+            if let Some(names) = &options.panels {
+                let panels: Vec<&dyn Fractal> = names
+                    .iter()
+                    .map(|name| find_fractal_or_error(&fractals, name))
+                    .collect::<Result<_, _>>()?;
+                for panel in &panels {
+                    check_polygon_flags(&options, panel.name(), panel.on_polygon())?;
+                }
+                let options = options.with_logged_seed();
+                if options.print_command {
+                    eprintln!("{}", reproducing_command(args, &options));
+                }
+                return generate_panels(&panels, &options);
+            }
+            let fractal = find_fractal_or_error(&fractals, command.unwrap_or(DEFAULT_FRACTAL))?;
+            check_polygon_flags(&options, fractal.name(), fractal.on_polygon())?;
+            if options.estimate_memory {
+                let estimate = Estimate::of(&options);
+                println!("rows: {}", estimate.rows);
+                println!("output: {}", human_bytes(estimate.output_bytes));
+                println!("memory: {}", human_bytes(estimate.memory_bytes));
+                Ok(())
+            } else {
+                let options = options.with_logged_seed();
+                if options.print_command {
+                    eprintln!("{}", reproducing_command(args, &options));
+                }
+                generate(fractal, &options)
+            }
+        }
+    }
+}
+
+/// The `chaos-game` command line that reproduces a run of `options`, parsed from `args`: the
+/// arguments as given, without `--print-command`, plus the resolved iterations, seed, and jump
+/// distance when they didn't come from a flag. Arguments the shell would mangle are quoted.
+fn reproducing_command(args: &[String], options: &Options) -> String {
+    let given = |flag: &str| args.iter().any(|arg| arg == flag);
+    let mut command: Vec<String> = args
+        .iter()
+        .filter(|arg| *arg != "--print-command")
+        .cloned()
+        .collect();
+    if !given("--iterations") && options.until_coverage.is_none() && !options.auto_iterations {
+        command.extend(vec![
+            "--iterations".to_string(),
+            options.iterations().to_string(),
+        ]);
+    }
+    if let (false, Some(jumps)) = (given("--jump-distance"), &options.jump_distance) {
+        let jumps: Vec<String> = jumps.iter().map(|jump| jump.0.to_string()).collect();
+        command.extend(vec!["--jump-distance".to_string(), jumps.join(",")]);
+    }
+    if let (false, Some(seed)) = (given("--seed"), options.seed) {
+        command.extend(vec!["--seed".to_string(), seed.to_string()]);
+    }
+
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./,:=+@%".contains(c);
+    let quote = |arg: String| {
+        if !arg.is_empty() && arg.chars().all(plain) {
+            arg
+        } else {
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        }
+    };
+    std::iter::once("chaos-game".to_string())
+        .chain(command.into_iter().map(quote))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Turns down the flags only the polygon fractals understand when `name` doesn't play on a polygon
+fn check_polygon_flags(options: &Options, name: &str, on_polygon: bool) -> Result<(), ChaosError> {
+    match options.polygon_flag() {
+        Some(flag) if !on_polygon => Err(ChaosError::Invalid(format!(
+            "{} only applies to the polygon fractals, {} doesn't play on a polygon",
+            flag, name
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn find_fractal<'a>(fractals: &'a [Box<dyn Fractal>], name: &str) -> Option<&'a dyn Fractal> {
+    fractals
+        .iter()
+        .find(|fractal| fractal.name() == name)
+        .map(|fractal| fractal.as_ref())
+}
+
+/// Like `find_fractal` but with an error pointing at `list` when there's no such fractal, since
+/// it's usually a typo rather than a bug
+fn find_fractal_or_error<'a>(
+    fractals: &'a [Box<dyn Fractal>],
+    name: &str,
+) -> Result<&'a dyn Fractal, ChaosError> {
+    find_fractal(fractals, name).ok_or_else(|| {
+        ChaosError::Invalid(format!(
+            "{} is not a fractal, `chaos-game list` shows the ones there are",
+            name
+        ))
+    })
+}
+
+/// Concatenates the point files named after `merge`, dropping repeated points with
+/// `--dedup-epsilon`, and writes the rows like a generated fractal would be
+fn merge(options: &Options) -> Result<(), ChaosError> {
+    let files: Vec<(&String, String)> = options
+        .arguments
+        .iter()
+        .map(|path| Ok((path, read_file(path)?)))
+        .collect::<Result<_, ChaosError>>()?;
+    let mut rows: Vec<(Point, &str)> = Vec::new();
+    for (path, text) in &files {
+        rows.extend(
+            parse_points(text).map_err(|err| ChaosError::in_file(path)(ChaosError::Parse(err)))?,
+        );
+    }
+    let points: Vec<Point> = rows.iter().map(|(point, _)| *point).collect();
+    let kept = match options.dedup_epsilon {
+        Some(epsilon) => dedup_points(&points, epsilon),
+        None => (0..rows.len()).collect(),
+    };
+
+    diagnostic!(
+        "merging {} points from {} files into {}",
+        rows.len(),
+        files.len(),
+        kept.len()
+    );
+    let mut output = Output::new(options, &points)?;
+    for i in kept {
+        output.emit_row(rows[i].1.to_string())?;
+    }
+    output.finish()
+}
+
+/// The polyline in `--vertices-file`, or a triangle without one
+fn base_vertices(options: &Options) -> Result<Vec<Point>, ChaosError> {
+    match &options.vertices_file {
+        Some(path) => Ok(parse_points(&read_file(path)?)
+            .map_err(|err| ChaosError::in_file(path)(ChaosError::Parse(err)))?
+            .into_iter()
+            .map(|(point, _)| point)
+            .collect()),
+        None => Ok(RegularPolygon::new(3, 50.0, Point::new(50.0, 50.0)).points()),
+    }
+}
+
+/// Draws a subdivision curve for `subdivide`, starting from the polyline in `--vertices-file` or
+/// a triangle
+fn subdivide(options: &Options) -> Result<(), ChaosError> {
+    let base = base_vertices(options)?;
+    if base.len() < 2 {
+        return Err(ChaosError::Invalid(
+            "subdivide needs at least two vertices".to_string(),
+        ));
+    }
+    let rule = options.rule.clone().unwrap_or_else(subdivision::Rule::koch);
+    let curve = subdivision::subdivide(&base, !options.open, &rule, options.depth);
+
+    diagnostic!(
+        "subdividing {} edges into {} points",
+        base.len(),
+        curve.len()
+    );
+    let mut output = Output::new(options, &base)?;
+    for point in curve {
+        if output.is_full() {
+            break;
+        }
+        output.emit(point, &[])?;
+    }
+    output.finish()
+}
+
+/// The odd entries of the first `rows` rows of Pascal's triangle, as the column of each odd entry
+/// in every row. Each row is the one above XORed with itself shifted by one, which is Pascal's
+/// rule mod 2 and the same thing as the [Rule 90](https://en.wikipedia.org/wiki/Rule_90)
+/// cellular automaton.
+fn pascal_odd_entries(rows: usize) -> impl Iterator<Item = Vec<usize>> {
+    let mut row = vec![true];
+    (0..rows).map(move |_| {
+        let odd = row
+            .iter()
+            .enumerate()
+            .filter(|(_, odd)| **odd)
+            .map(|(column, _)| column)
+            .collect();
+        let next = (0..=row.len())
+            .map(|k| {
+                let left = k > 0 && row[k - 1];
+                let right = k < row.len() && row[k];
+                left != right
+            })
+            .collect();
+        row = next;
+        odd
+    })
+}
+
+/// Prints a point for every odd entry of Pascal's triangle for `pascal`, row 0 at the top and each
+/// row centered under the one above. An exact Sierpinski triangle to compare the chaos game with.
+fn pascal(options: &Options) -> Result<(), ChaosError> {
+    let rows = options.rows.unwrap_or(PASCAL_ROWS);
+    let top = rows.saturating_sub(1) as f64;
+    let frame = [
+        Point::new(-top / 2.0, 0.0),
+        Point::new(top / 2.0, 0.0),
+        Point::new(0.0, top),
+    ];
+
+    diagnostic!("computing {} rows of pascal's triangle", rows);
+    let mut output = Output::new(options, &frame)?;
+    for (row, odd) in pascal_odd_entries(rows).enumerate() {
+        for column in odd {
+            if output.is_full() {
+                break;
+            }
+            let x = column as f64 - row as f64 / 2.0;
+            output.emit(Point::new(x, top - row as f64), &[])?;
+        }
+    }
+    output.finish()
+}
+
+/// The angles `rose` steps through to trace the whole curve `r = cos(k theta)` once. Written as
+/// a fraction `k = p/q` in lowest terms the curve closes after `q` half turns when `p q` is odd,
+/// whose petals repeat after that, and `q` full turns otherwise, e.g. half a turn for 3, a full
+/// turn for 4, and two full turns for 3/2.
+fn rose_turn(k: f64) -> f64 {
+    let q = (1..=ROSE_MAX_DENOMINATOR)
+        .find(|&q| {
+            let scaled = k * q as f64;
+            (scaled - scaled.round()).abs() < 1e-9 * scaled.abs().max(1.0)
+        })
+        .unwrap_or(ROSE_MAX_DENOMINATOR);
+    let p = (k * q as f64).round().abs() as u64;
+    let half_turns = if p % 2 == 1 && q % 2 == 1 { q } else { 2 * q };
+    half_turns as f64 * std::f64::consts::PI
+}
+
+/// Prints `--points` evenly spaced points along the
+/// [rose](https://en.wikipedia.org/wiki/Rose_(mathematics)) `r = cos(k theta)` for `rose`,
+/// centered on the origin with petals `ROSE_RADIUS` long
+fn rose(options: &Options) -> Result<(), ChaosError> {
+    let k = options.k.unwrap_or(ROSE_K);
+    let points = options.points.unwrap_or(ROSE_POINTS);
+    let turn = rose_turn(k);
+    let frame = [
+        Point::new(-ROSE_RADIUS, -ROSE_RADIUS),
+        Point::new(ROSE_RADIUS, ROSE_RADIUS),
+    ];
+
+    let mut output = Output::new(options, &frame)?;
+    for i in 0..points {
+        if output.is_full() {
+            break;
+        }
+        let theta = turn * i as f64 / points as f64;
+        output.emit(
+            Point::from_polar(ROSE_RADIUS * (k * theta).cos(), theta),
+            &[],
+        )?;
+    }
+    output.finish()
+}
+
+/// A circle described by its curvature, 1 / radius, and negative for a circle enclosing the others
+/// it's tangent to. Curvatures and curvature-weighted centers are what Descartes' theorem relates.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Circle {
+    center: Point,
+    curvature: f64,
+}
+
+impl Circle {
+    fn radius(&self) -> f64 {
+        1.0 / self.curvature.abs()
+    }
+
+    /// The other circle tangent to `a`, `b`, and `c` than `self`, which is tangent to them too.
+    /// By [Descartes' theorem](https://en.wikipedia.org/wiki/Descartes%27_theorem) the two
+    /// solutions' curvatures add up to twice the sum of the three, and so do their curvatures
+    /// times their centers.
+    fn swap(&self, a: &Circle, b: &Circle, c: &Circle) -> Circle {
+        let curvature = 2.0 * (a.curvature + b.curvature + c.curvature) - self.curvature;
+        let weighted =
+            |circle: &Circle, axis: fn(&Point) -> f64| circle.curvature * axis(&circle.center);
+        let center = |axis: fn(&Point) -> f64| {
+            (2.0 * (weighted(a, axis) + weighted(b, axis) + weighted(c, axis))
+                - weighted(self, axis))
+                / curvature
+        };
+        Circle {
+            center: Point::new(center(|point| point.x), center(|point| point.y)),
+            curvature,
+        }
+    }
+}
+
+/// The circles of an [Apollonian gasket](https://en.wikipedia.org/wiki/Apollonian_gasket) in the
+/// unit circle, starting from curvatures -1, 2, 2, 3, 3 and filling every gap between three
+/// tangent circles with the largest circle that fits, `depth` times over
+fn apollonian_circles(depth: usize) -> Vec<Circle> {
+    fn fill(a: Circle, b: Circle, c: Circle, other: Circle, depth: usize, out: &mut Vec<Circle>) {
+        let new = other.swap(&a, &b, &c);
+        out.push(new);
+        if depth > 0 {
+            fill(a, b, new, c, depth - 1, out);
+            fill(a, c, new, b, depth - 1, out);
+            fill(b, c, new, a, depth - 1, out);
+        }
+    }
+
+    let circle = |x, y, curvature| Circle {
+        center: Point::new(x, y),
+        curvature,
+    };
+    let outer = circle(0.0, 0.0, -1.0);
+    let left = circle(-0.5, 0.0, 2.0);
+    let right = circle(0.5, 0.0, 2.0);
+    let top = circle(0.0, 2.0 / 3.0, 3.0);
+    let bottom = circle(0.0, -2.0 / 3.0, 3.0);
+
+    let mut circles = vec![outer, left, right, top, bottom];
+    // Each of the middle two leaves three gaps, between it and two of the first three circles
+    for &middle in &[top, bottom] {
+        fill(outer, left, middle, right, depth, &mut circles);
+        fill(outer, right, middle, left, depth, &mut circles);
+        fill(left, right, middle, outer, depth, &mut circles);
+    }
+    circles
+}
+
+/// Prints points spaced evenly around every circle of an Apollonian gasket `--depth` deep for
+/// `apollonian`, in a circle of radius `APOLLONIAN_RADIUS` centered on the origin. The outer
+/// circle gets `--points` points and the rest fewer in proportion to their radius.
+fn apollonian(options: &Options) -> Result<(), ChaosError> {
+    if options.depth > APOLLONIAN_MAX_DEPTH {
+        return Err(ChaosError::Invalid(format!(
+            "--depth can be at most {} for apollonian",
+            APOLLONIAN_MAX_DEPTH
+        )));
+    }
+    let points = options.points.unwrap_or(APOLLONIAN_POINTS);
+    let frame = [
+        Point::new(-APOLLONIAN_RADIUS, -APOLLONIAN_RADIUS),
+        Point::new(APOLLONIAN_RADIUS, APOLLONIAN_RADIUS),
+    ];
+
+    let circles = apollonian_circles(options.depth);
+    diagnostic!("drawing {} circles", circles.len());
+    let mut output = Output::new(options, &frame)?;
+    for circle in circles {
+        let radius = circle.radius();
+        let count = ((points as f64 * radius).round() as usize).max(APOLLONIAN_MIN_POINTS);
+        for i in 0..count {
+            if output.is_full() {
+                break;
+            }
+            let theta = 2.0 * std::f64::consts::PI * i as f64 / count as f64;
+            let point =
+                Point::from_polar(radius, theta).translate(circle.center.x, circle.center.y);
+            output.emit(
+                Point::new(point.x * APOLLONIAN_RADIUS, point.y * APOLLONIAN_RADIUS),
+                &[],
+            )?;
+        }
+    }
+    output.finish()
+}
+
+/// The `d`th cell along the order `order`
+/// [Hilbert curve](https://en.wikipedia.org/wiki/Hilbert_curve), which visits every cell of a
+/// `2^order` square grid, using the standard d2xy mapping. Each pair
+/// of bits of `d` picks a quadrant, rotating and flipping the ones below so the quadrants join up.
+fn hilbert_point(order: u32, d: u64) -> (u64, u64) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut size = 1;
+    while size < 1 << order {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = size - 1 - x;
+                y = size - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += size * rx;
+        y += size * ry;
+        t /= 4;
+        size *= 2;
+    }
+    (x, y)
+}
+
+/// Prints the `4^order` cells of the Hilbert curve in path order for `hilbert`, one unit apart on
+/// a grid from 0 to `2^order - 1`, for `plot '...' with lines`
+fn hilbert(options: &Options) -> Result<(), ChaosError> {
+    let order = options.order.unwrap_or(HILBERT_ORDER);
+    if order >= 32 {
+        return Err(ChaosError::Invalid(
+            "--order has to be less than 32".to_string(),
+        ));
+    }
+    let side = ((1_u64 << order) - 1) as f64;
+    let frame = [Point::new(0.0, 0.0), Point::new(side, side)];
+
+    let mut output = Output::new(options, &frame)?;
+    for d in 0..1_u64 << (2 * order) {
+        if output.is_full() {
+            break;
+        }
+        let (x, y) = hilbert_point(order, d);
+        output.emit(Point::new(x as f64, y as f64), &[])?;
+    }
+    output.finish()
+}
+
+/// Half of a Penrose rhombus, split along its long diagonal for the thin rhombus and its short
+/// one for the thick, with `a` at the apex and `b` to `c` along the diagonal
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct RobinsonTriangle {
+    thick: bool,
+    a: Point,
+    b: Point,
+    c: Point,
+}
+
+impl RobinsonTriangle {
+    /// Replaces the triangle with smaller ones, cutting its sides in the golden ratio: two for a
+    /// thin half and three for a thick one
+    fn subdivide(&self) -> Vec<RobinsonTriangle> {
+        let ratio = 2.0 / (1.0 + 5.0_f64.sqrt());
+        let (a, b, c) = (self.a, self.b, self.c);
+        let triangle = |thick, a, b, c| RobinsonTriangle { thick, a, b, c };
+        if self.thick {
+            let q = b.lerp(&a, ratio);
+            let r = b.lerp(&c, ratio);
+            vec![
+                triangle(true, r, c, a),
+                triangle(true, q, r, b),
+                triangle(false, r, q, a),
+            ]
+        } else {
+            let p = a.lerp(&b, ratio);
+            vec![triangle(false, c, p, b), triangle(true, p, c, a)]
+        }
+    }
+}
+
+/// The edges of the [Penrose rhombus tiling](https://en.wikipedia.org/wiki/Penrose_tiling) (P3)
+/// grown from a wheel of ten thin half rhombi around the origin by `depth` rounds of subdivision.
+/// The side from `b` to `c` is the diagonal inside a rhombus rather than an edge of one, so only
+/// the other two sides of each triangle are drawn, and the edges two triangles share appear once.
+fn penrose_edges(depth: usize) -> Vec<(Point, Point)> {
+    let mut triangles: Vec<RobinsonTriangle> = (0..10)
+        .map(|i| {
+            let angle = |step: i32| f64::from(step) * std::f64::consts::PI / 10.0;
+            let mut b = Point::from_polar(1.0, angle(2 * i - 1));
+            let mut c = Point::from_polar(1.0, angle(2 * i + 1));
+            // Mirror every other one so neighbors match up along their shared edges
+            if i % 2 == 0 {
+                std::mem::swap(&mut b, &mut c);
+            }
+            RobinsonTriangle {
+                thick: false,
+                a: Point::new(0.0, 0.0),
+                b,
+                c,
+            }
+        })
+        .collect();
+    for _ in 0..depth {
+        triangles = triangles
+            .iter()
+            .flat_map(RobinsonTriangle::subdivide)
+            .collect();
+    }
+
+    // Shared edges are computed from different triangles, so they only match to within rounding
+    let key = |point: &Point| {
+        (
+            (point.x * 1e9).round() as i64,
+            (point.y * 1e9).round() as i64,
+        )
+    };
+    let mut seen = std::collections::HashSet::new();
+    let mut edges = Vec::new();
+    for triangle in &triangles {
+        for &(from, to) in &[(triangle.c, triangle.a), (triangle.a, triangle.b)] {
+            let (first, second) = (key(&from), key(&to));
+            if seen.insert((first.min(second), first.max(second))) {
+                edges.push((from, to));
+            }
+        }
+    }
+    edges
+}
+
+/// Prints each edge of a Penrose tiling `--depth` rounds of subdivision deep for `penrose` as a
+/// pair of points followed by a blank line, in a circle of radius `PENROSE_RADIUS`
+fn penrose(options: &Options) -> Result<(), ChaosError> {
+    if options.depth > PENROSE_MAX_DEPTH {
+        return Err(ChaosError::Invalid(format!(
+            "--depth can be at most {} for penrose",
+            PENROSE_MAX_DEPTH
+        )));
+    }
+    let frame = [
+        Point::new(-PENROSE_RADIUS, -PENROSE_RADIUS),
+        Point::new(PENROSE_RADIUS, PENROSE_RADIUS),
+    ];
+
+    let scaled = |point: Point| Point::new(point.x * PENROSE_RADIUS, point.y * PENROSE_RADIUS);
+    let edges = penrose_edges(options.depth);
+    diagnostic!("drawing {} edges", edges.len());
+    let mut output = Output::new(options, &frame)?;
+    for (from, to) in edges {
+        if output.is_full() {
+            break;
+        }
+        output.emit(scaled(from), &[])?;
+        output.emit(scaled(to), &[])?;
+        output.break_path()?;
+    }
+    output.finish()
+}
+
+/// `base` followed by its medial polygon, that polygon's medial polygon, and so on `depth` times
+fn medial_polygons(base: Vec<Point>, depth: usize) -> Vec<GenericPolygon> {
+    let mut levels = vec![GenericPolygon::new(base)];
+    for _ in 0..depth {
+        let next = GenericPolygon::new(levels[levels.len() - 1].medial_points());
+        levels.push(next);
+    }
+    levels
+}
+
+/// Prints the polygon in `--vertices-file` or a triangle and its nested medial polygons for
+/// `medial`, each closed and followed by a blank line with its level as a group column
+fn medial(options: &Options) -> Result<(), ChaosError> {
+    let base = base_vertices(options)?;
+    if base.len() < 3 {
+        return Err(ChaosError::Invalid(
+            "medial needs at least three vertices".to_string(),
+        ));
+    }
+    let levels = medial_polygons(base.clone(), options.medial_depth.unwrap_or(MEDIAL_DEPTH));
+    diagnostic!("drawing {} polygons", levels.len());
+
+    let mut output = Output::new(options, &base)?;
+    for (level, polygon) in levels.iter().enumerate() {
+        if output.is_full() {
+            break;
+        }
+        for point in polygon.points.iter().chain(polygon.points.first()) {
+            output.emit(*point, &[level as f64])?;
+        }
+        output.break_path()?;
+    }
+    output.finish()
+}
+
+/// Times `run`'s core loop for `bench`: the Sierpinski triangle played for `--iterations` rounds
+/// from `--seed` (0 by default), with the points folded into a checksum instead of written so
+/// formatting and I/O aren't measured. Prints the rounds, seconds, and points per second.
+fn bench(options: &Options) -> Result<(), ChaosError> {
+    let triangle = Triangle::new_equilateral(100.0);
+    let points = triangle.points();
+    let iterations = options.iterations();
+    let mut rng = StdRng::seed_from_u64(options.seed.unwrap_or(0));
+
+    let start = std::time::Instant::now();
+    let mut checksum = 0.0;
+    triangle.play(
+        &mut rng,
+        iterations,
+        0.5,
+        &points,
+        &mut |rng| rng.gen_range(0, points.len()),
+        &mut |point, _| {
+            checksum += point.x + point.y;
+            true
+        },
+    );
+    let seconds = start.elapsed().as_secs_f64();
+    std::hint::black_box(checksum);
+
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "iterations: {}", iterations)?;
+    writeln!(stdout, "seconds: {}", seconds)?;
+    writeln!(stdout, "points/sec: {:.0}", iterations as f64 / seconds)?;
+    Ok(())
+}
+
+fn generate(fractal: &dyn Fractal, options: &Options) -> Result<(), ChaosError> {
+    let declared_options;
+    let options = if options.verify || options.cell_count_check || options.meta.is_some() {
+        declared_options = Options {
+            holes: fractal.holes(),
+            self_similarity: fractal.self_similarity(),
+            restriction: fractal.restriction(),
+            ..options.clone()
+        };
+        &declared_options
+    } else {
+        options
+    };
+    if options.cell_count_check && options.self_similarity.is_none() {
+        diagnostic!(
+            "cells: {} doesn't declare how it's made of copies of itself to check",
+            fractal.name()
+        );
+    }
+    let iterations = options.iterations();
+    match options.until_coverage {
+        Some(fraction) => diagnostic!(
+            "generating points for {} until {}% coverage, up to {}",
+            fractal.name(),
+            fraction * 100.0,
+            iterations
+        ),
+        None if options.auto_iterations && options.iterations.is_none() => {
+            diagnostic!("generating points for {}", fractal.name())
+        }
+        None => diagnostic!("generating {} points for {}", iterations, fractal.name()),
+    }
+    if options.trajectory && iterations > TRAJECTORY_LIMIT {
+        diagnostic!(
+            "warning: a trajectory of {} points is too dense to follow, try --iterations {}",
+            iterations,
+            TRAJECTORY_LIMIT
+        );
+    }
+
+    match options.threads {
+        Some(threads) if threads > 1 => generate_chains(fractal, options, threads),
+        _ => fractal.run(options),
+    }
+}
+
+/// Runs `chains` independent games on their own threads, splitting the iterations between them,
+/// and writes their rows one chain after another. Each chain is seeded from `--seed` so the
+/// merged output is as reproducible as a single game.
+fn generate_chains(
+    fractal: &dyn Fractal,
+    options: &Options,
+    chains: usize,
+) -> Result<(), ChaosError> {
+    let master_seed = options.seed.unwrap_or_else(rand::random);
+    let iterations = options.iterations();
+    let files = (0..chains)
+        .map(|chain| TempFile::new(&format!("chain-{}", chain)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    std::thread::scope(|scope| {
+        let mut handles = Vec::new();
+        for (chain, file) in files.iter().enumerate() {
+            // Everything that limits or redirects rows applies to the merged rows instead
+            let chain_options = Options {
+                iterations: Some(iterations / chains + usize::from(chain < iterations % chains)),
+                seed: Some(chain_seed(master_seed, chain)),
+                output: Some(file.path.clone()),
+                append: false,
+                sample: None,
+                max_points: None,
+                split: None,
+                threads: None,
+                points_per_line: None,
+                ..options.clone()
+            };
+            handles.push(scope.spawn(move || fractal.run(&chain_options)));
+        }
+        handles
+            .into_iter()
+            .try_for_each(|handle| handle.join().expect("A chain panicked"))
+    })?;
+
+    // The chains already tiled, mirrored, and transformed their points
+    let merged_options = Options {
+        tile: None,
+        mirror: None,
+        rotate_output: None,
+        invert_y: false,
+        affine: None,
+        ..options.clone()
+    };
+    let mut output = Output::new(&merged_options, &[])?;
+    for file in files {
+        let text = read_file(&file.path)?;
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            output.emit_row(line.to_string())?;
+        }
+    }
+    output.finish()
+}
+
+/// Plays each of `panels` for `--panels` and writes their points side by side in a grid, `--cols`
+/// panels to a row, with a group column numbering them. Like `generate_chains` each fractal
+/// writes to a temporary file first, in plain unscaled coordinates so they can be moved into
+/// place, and the output flags are applied as the points are copied over.
+fn generate_panels(panels: &[&dyn Fractal], options: &Options) -> Result<(), ChaosError> {
+    diagnostic!(
+        "generating {} points for each of {} panels",
+        options.iterations(),
+        panels.len()
+    );
+    let columns = options
+        .cols
+        .unwrap_or_else(|| (panels.len() as f64).sqrt().ceil() as usize)
+        .max(1);
+
+    let mut runs = Vec::new();
+    for (panel, fractal) in panels.iter().enumerate() {
+        let file = TempFile::new(&format!("panel-{}", panel))?;
+        let panel_options = Options {
+            output: Some(file.path.clone()),
+            append: false,
+            sample: None,
+            max_points: None,
+            split: None,
+            stats: false,
+            nn_stats: false,
+            format: Format::Cartesian,
+            scale: None,
+            integer: false,
+            sig_figs: None,
+            delta: false,
+            points_per_line: None,
+            grid_unique: None,
+            meta: None,
+            holes: fractal.holes(),
+            self_similarity: fractal.self_similarity(),
+            ..options.clone()
+        };
+        fractal.run(&panel_options)?;
+        let text = read_file(&file.path)?;
+        drop(file);
+        let rows: Vec<(Point, Vec<f64>)> = parse_points(&text)
+            .map_err(|err| ChaosError::Parse(format!("{}: {}", fractal.name(), err)))?
+            .into_iter()
+            .map(|(point, row)| {
+                let columns = row.split_whitespace().skip(2).map(|value| {
+                    value.parse().map_err(|_| {
+                        ChaosError::Parse(format!("{}: {} isn't a number", fractal.name(), value))
+                    })
+                });
+                Ok((point, columns.collect::<Result<_, _>>()?))
+            })
+            .collect::<Result<_, ChaosError>>()?;
+        runs.push(rows);
+    }
+
+    let bounds: Vec<Option<Bounds>> = runs
+        .iter()
+        .map(|rows| {
+            let points: Vec<Point> = rows.iter().map(|(point, _)| *point).collect();
+            Some(Bounds::of(&points)).filter(|_| !points.is_empty())
+        })
+        .collect();
+    let largest = bounds
+        .iter()
+        .flatten()
+        .map(|bounds| bounds.width().max(bounds.height()))
+        .fold(0.0, f64::max);
+    let pitch = largest * (1.0 + PANEL_GAP);
+    let rows = panels.len().div_ceil(columns);
+    let frame = [
+        Point::new(0.0, -((rows - 1) as f64) * pitch),
+        Point::new(columns as f64 * pitch, pitch),
+    ];
+
+    // The panels already tiled, mirrored, transformed, thinned, and checked their points
+    let merged_options = Options {
+        tile: None,
+        mirror: None,
+        rotate_output: None,
+        invert_y: false,
+        affine: None,
+        every: 1,
+        decimate_adaptive: None,
+        clip_to_polygon: false,
+        until_coverage: None,
+        verify: false,
+        ..options.clone()
+    };
+    let mut output = Output::new(&merged_options, &frame)?;
+    for (panel, (points, bounds)) in runs.into_iter().zip(bounds).enumerate() {
+        let bounds = match bounds {
+            Some(bounds) => bounds,
+            None => continue,
+        };
+        // Centered in its cell, the first row along the top
+        let (column, row) = ((panel % columns) as f64, (panel / columns) as f64);
+        let dx = column * pitch + (pitch - bounds.width()) / 2.0 - bounds.min.x;
+        let dy = -row * pitch + (pitch - bounds.height()) / 2.0 - bounds.min.y;
+        for (point, mut columns) in points {
+            if output.is_full() {
+                break;
+            }
+            columns.push(panel as f64);
+            output.emit(point.translate(dx, dy), &columns)?;
+        }
+    }
+    output.finish()
+}
+
+/// The seed of chain `chain` of a `--threads` run, mixed from the master seed with
+/// [SplitMix64](https://prng.di.unimi.it/splitmix64.c) so neighboring chains aren't correlated
+fn chain_seed(master_seed: u64, chain: usize) -> u64 {
+    let mut z = master_seed.wrapping_add((chain as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[allow(unused)]
+fn sierpinski_triangle(options: &Options) -> Result<(), ChaosError> {
+    let jump_distance = 0.5;
+    let triangle = Triangle::new_equilateral(100.0);
+    let mut rng = options.rng();
+    let points = triangle.points();
+    triangle.chaos_game(options, &mut rng, jump_distance, &points, &mut |rng| {
+        rng.gen_range(0, points.len())
+    })
+}
+
+/// The restriction that the next vertex can't be the one just picked. It draws again until it
+/// gets a different one.
+struct NoRepeat {
+    previous: Option<usize>,
+}
+
+impl NoRepeat {
+    /// Picks one of `count` vertices uniformly, other than the previous one
+    fn pick<R: Rng>(&mut self, rng: &mut R, count: usize) -> usize {
+        loop {
+            let vertex = rng.gen_range(0, count);
+            if Some(vertex) != self.previous {
+                self.previous = Some(vertex);
+                return vertex;
+            }
+        }
+    }
+}
+
+#[allow(unused)]
+fn square_one(options: &Options) -> Result<(), ChaosError> {
+    let jump_distance = 0.5;
+    let square = Quadrilateral::square(100.0);
+    let points = square.points();
+
+    let mut rng = options.rng();
+    let mut no_repeat = NoRepeat {
+        previous: Some(rng.gen_range(0, points.len())),
+    };
+    square.chaos_game(options, &mut rng, jump_distance, &points, &mut |rng| {
+        no_repeat.pick(rng, points.len())
+    })
+}
+
+/// The Sierpinski triangle without jumping towards the same vertex twice in a row. Unlike the
+/// square, where the rule is what gives it any structure at all, the triangle keeps its gaps but
+/// every copy loses the smaller copy in the corner it shares with the whole, since landing there
+/// takes two jumps in a row towards that corner.
+#[allow(unused)]
+fn sierpinski_triangle_no_repeat(options: &Options) -> Result<(), ChaosError> {
+    let jump_distance = 0.5;
+    let triangle = Triangle::new_equilateral(100.0);
+    let points = triangle.points();
+
+    let mut rng = options.rng();
+    let mut no_repeat = NoRepeat {
+        previous: Some(rng.gen_range(0, points.len())),
+    };
+    triangle.chaos_game(options, &mut rng, jump_distance, &points, &mut |rng| {
+        no_repeat.pick(rng, points.len())
+    })
+}
+
+#[allow(unused)]
+fn square_two(options: &Options) -> Result<(), ChaosError> {
+    let jump_distance = 0.5;
+    let square = Quadrilateral::square(100.0);
+    let points = square.points();
+
+    let mut rng = options.rng();
+    let mut prev_vertex = rng.gen_range(0, points.len());
+    square.chaos_game(options, &mut rng, jump_distance, &points, &mut |rng| loop {
+        let new_vertex = rng.gen_range(0, points.len());
+        let (new, prev) = (points[new_vertex], points[prev_vertex]);
+        if (new.x == prev.x || new.y == prev.y) {
+            prev_vertex = new_vertex;
+            break new_vertex;
+        }
+    })
+}
+
+/// The middle third of each side of the square `vicsek` is built on, between the copies in the
+/// corners and the one in the center
+fn vicsek_holes() -> Vec<Vec<Point>> {
+    let third = 100.0 / 3.0;
+    let square = |x: f64, y: f64| {
+        vec![
+            Point::new(x, y),
+            Point::new(x + third, y),
+            Point::new(x + third, y + third),
+            Point::new(x, y + third),
+        ]
+    };
+    vec![
+        square(third, 0.0),
+        square(2.0 * third, third),
+        square(third, 2.0 * third),
+        square(0.0, third),
+    ]
+}
+
+#[allow(unused)]
+fn vicsek_fractal(options: &Options) -> Result<(), ChaosError> {
+    let jump_distance = 0.66666666667;
+    let square = Quadrilateral::square(100.0);
+    let mut points = square.points();
+    let midpoint = points[0].midpoint(&points[2]);
+    points.push(midpoint);
+
+    let mut rng = options.rng();
+    square.chaos_game(options, &mut rng, jump_distance, &points, &mut |rng| {
+        rng.gen_range(0, points.len())
+    })
+}
+
+#[allow(unused)]
+fn nflake(options: &Options) -> Result<(), ChaosError> {
+    let sides = options.sides.unwrap_or(5);
+    let jump_distance = 1.0 - nflake_ratio(sides);
+    let polygon = RegularPolygon::new(sides, 50.0, Point::new(50.0, 50.0))
+        .with_start_angle(options.theta_offset());
+    let points = polygon.points();
+
+    let mut rng = options.rng();
+    polygon.chaos_game(options, &mut rng, jump_distance, &points, &mut |rng| {
+        rng.gen_range(0, points.len())
+    })
+}
+
+/// The octaflake, `nflake` with eight sides under its own name. Copies scaled by
+/// `nflake_ratio(8) = 1/(2 + sqrt(2))` around the vertices meet corner to corner.
+#[allow(unused)]
+fn sierpinski_octagon(options: &Options) -> Result<(), ChaosError> {
+    let jump_distance = 1.0 - nflake_ratio(8);
+    let octagon = RegularPolygon::new(8, 50.0, Point::new(50.0, 50.0))
+        .with_start_angle(options.theta_offset());
+    let points = octagon.points();
+
+    let mut rng = options.rng();
+    octagon.chaos_game(options, &mut rng, jump_distance, &points, &mut |rng| {
+        rng.gen_range(0, points.len())
+    })
+}
+
+/// A hybrid of the square and triangle fractals, playing on all seven vertices at once
+#[allow(unused)]
+fn triangle_in_square(options: &Options) -> Result<(), ChaosError> {
+    let jump_distance = 0.5;
+    let shape = CompositePolygon(vec![
+        Box::new(Quadrilateral::square(100.0)),
+        Box::new(RegularPolygon::new(3, 40.0, Point::new(50.0, 50.0))),
+    ]);
+    let points = shape.points();
+
+    let mut rng = options.rng();
+    shape.chaos_game(options, &mut rng, jump_distance, &points, &mut |rng| {
+        rng.gen_range(0, points.len())
+    })
+}
+
+/// The [hexaflake](https://en.wikipedia.org/wiki/N-flake#Hexaflake), which is the hexagon
+/// `nflake` with a seventh copy in the middle. Six hexagons scaled by `nflake_ratio(6) = 1/3`
+/// around the vertices touch edge to edge and leave a hole that's exactly another hexagon of the
+/// same size, so jumping 2/3 of the way to the center as well fills it without overlapping.
+#[allow(unused)]
+fn hexaflake(options: &Options) -> Result<(), ChaosError> {
+    let jump_distance = 1.0 - nflake_ratio(6);
+    let hexagon = RegularPolygon::new(6, 50.0, Point::new(50.0, 50.0))
+        .with_start_angle(options.theta_offset());
+    let mut points = hexagon.points();
+    points.push(Point::centroid(&points));
+
+    let mut rng = options.rng();
+    hexagon.chaos_game(options, &mut rng, jump_distance, &points, &mut |rng| {
+        rng.gen_range(0, points.len())
+    })
+}
+
+/// The [Heighway dragon](https://en.wikipedia.org/wiki/Dragon_curve) as two maps that each
+/// shrink by 1/sqrt(2) towards their fixed point and rotate around it, by 45 and 135 degrees.
+#[allow(unused)]
+fn heighway_dragon(options: &Options) -> Result<(), ChaosError> {
+    let jump_distance = 1.0 - std::f64::consts::FRAC_1_SQRT_2;
+    let targets = [
+        (Point::new(0.0, 0.0), 45.0_f64.to_radians()),
+        (Point::new(60.0, 20.0), 135.0_f64.to_radians()),
+    ];
+
+    // The dragon spills well past its two fixed points, this is the box around it
+    let frame = [
+        Point::new(-100.0 / 3.0, -100.0 / 3.0),
+        Point::new(350.0 / 3.0, 200.0 / 3.0),
+    ];
+
+    let mut rng = options.rng();
+    let mut current_point = targets[0].0.midpoint(&targets[1].0);
+    let mut output = Output::new(options, &frame)?;
+    for _ in 0..options.iterations() {
+        if output.is_full() {
+            break;
+        }
+        output.emit(current_point, &[]);
+        let (target, angle) = targets.choose(&mut rng).expect("Shouldn't be empty");
+        current_point = current_point.jump_towards_rotated(target, jump_distance, *angle);
+    }
+    output.finish()
+}
+
+/// Prints `x y z` rows that can be viewed with `splot 'plots/tetrahedron.txt' with points`.
+/// Rotating the vertices up front rotates the whole cloud since every point is a mix of them.
+/// With `--depth-shade` a fourth column runs from 0 at the lowest vertex to 1 at the highest.
+#[allow(unused)]
+fn sierpinski_tetrahedron(options: &Options) -> Result<(), ChaosError> {
+    let jump_distance = 0.5;
+    let length = 100.0;
+    let height = length * 3.0_f64.sqrt() / 2.0;
+    let vertices: Vec<Point3> = [
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(length, 0.0, 0.0),
+        Point3::new(length / 2.0, height, 0.0),
+        Point3::new(length / 2.0, height / 3.0, length * (2.0_f64 / 3.0).sqrt()),
+    ]
+    .iter()
+    .map(|p| {
+        p.rotate_x(options.rotate_x.to_radians())
+            .rotate_y(options.rotate_y.to_radians())
+            .rotate_z(options.rotate_z.to_radians())
+    })
+    .collect();
+
+    // Every point is a mix of the vertices so none is lower or higher than all of them
+    let lowest = vertices.iter().map(|p| p.z).fold(f64::INFINITY, f64::min);
+    let highest = vertices
+        .iter()
+        .map(|p| p.z)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let shade = |z: f64| (z - lowest) / (highest - lowest);
+
+    let mut rng = options.rng();
+    let mut current_point = vertices[0].jump_towards(&vertices[1], 0.5);
+    let projected: Vec<Point> = vertices.iter().map(|p| Point::new(p.x, p.y)).collect();
+    let mut output = Output::new(options, &projected)?;
+    for _ in 0..options.iterations() {
+        if output.is_full() {
+            break;
+        }
+        let projected = Point::new(current_point.x, current_point.y);
+        if options.depth_shade {
+            output.emit(projected, &[current_point.z, shade(current_point.z)]);
+        } else {
+            output.emit(projected, &[current_point.z]);
+        }
+        let reference_point = vertices.choose(&mut rng).expect("Shouldn't be empty");
+        current_point = current_point.jump_towards(reference_point, jump_distance);
+    }
+    output.finish()
+}
+
+/// The [Julia set](https://en.wikipedia.org/wiki/Julia_set) of `z -> z^2 + c` by inverse
+/// iteration. Undoing the map has two branches, `z -> ±sqrt(z - c)`, and picking one at random
+/// each time is a chaos game whose attractor is the Julia set, the same way picking a vertex is
+/// for the polygon fractals.
+#[allow(unused)]
+fn julia(options: &Options) -> Result<(), ChaosError> {
+    let (re, im) = options.c.unwrap_or(JULIA_C);
+    let c = Point::new(re, im);
+
+    // Every point of the set is within max(|c|, 2) of the origin
+    let radius = c.distance(&Point::new(0.0, 0.0)).max(2.0);
+    let frame = [Point::new(-radius, -radius), Point::new(radius, radius)];
+
+    let mut rng = options.rng();
+    let mut current_point = Point::new(1.0, 0.0);
+    // Let the point settle onto the set before writing anything
+    for _ in 0..JULIA_SETTLE {
+        current_point = Point::new(current_point.x - c.x, current_point.y - c.y).complex_sqrt();
+    }
+    let mut output = Output::new(options, &frame)?;
+    for _ in 0..options.iterations() {
+        if output.is_full() {
+            break;
+        }
+        output.emit(current_point, &[]);
+        let root = Point::new(current_point.x - c.x, current_point.y - c.y).complex_sqrt();
+        current_point = if rng.gen() {
+            root
+        } else {
+            Point::new(-root.x, -root.y)
+        };
+    }
+    output.finish()
+}
+
+/// The chaos game with a continuous boundary in place of the vertices: each jump goes towards
+/// `(a cos t, b sin t)` for a uniformly random `t`. With no gaps between targets there are no
+/// gaps in the attractor either, it's a fuzzy cloud filling the ellipse, densest in the middle
+/// and thinning out towards the boundary, which it never reaches. Shorter `--jump-distance`s
+/// squeeze it into a tighter blob and longer ones push it out into a ring just inside the ellipse.
+#[allow(unused)]
+fn ellipse_attractor(options: &Options) -> Result<(), ChaosError> {
+    let jump_distances = options.jump_distances(0.5);
+    let (a, b) = (
+        options.a.unwrap_or(ELLIPSE_A),
+        options.b.unwrap_or(ELLIPSE_B),
+    );
+    let frame = [Point::new(-a, -b), Point::new(a, b)];
+
+    let mut rng = options.rng();
+    let mut current_point = Point::new(0.0, 0.0);
+    let mut output = Output::new(options, &frame)?;
+    for i in 0..options.iterations() {
+        if output.is_full() {
+            break;
+        }
+        output.emit(current_point, &[]);
+        let t = rng.gen_range(0.0, 2.0 * std::f64::consts::PI);
+        let target = Point::new(a * t.cos(), b * t.sin());
+        let jump_distance = jump_distances.next(&mut rng, i);
+        current_point = current_point.jump_towards(&target, jump_distance);
+    }
+    output.finish()
+}
+
+/// Jumps halfway towards a random vertex of the polygon read from `--svg-input`, the triangle
+/// `sierpinski-triangle` is built on without one
+#[allow(unused)]
+fn svg_polygon(options: &Options) -> Result<(), ChaosError> {
+    let polygon = match &options.svg_input {
+        Some(path) => GenericPolygon::new(
+            svg_input::parse(&read_file(path)?)
+                .map_err(|err| ChaosError::in_file(path)(ChaosError::Invalid(err)))?,
+        ),
+        None => GenericPolygon::new(Triangle::new_equilateral(100.0).points()),
+    };
+    let points = polygon.points();
+
+    let jump_distance = 0.5;
+    let mut rng = options.rng();
+    polygon.chaos_game(options, &mut rng, jump_distance, &points, &mut |rng| {
+        rng.gen_range(0, points.len())
+    })
+}
+
+/// An arbitrary iterated function system read from `--config`. There's no polygon to jump
+/// towards, each iteration maps the point with one of the transforms.
+#[allow(unused)]
+fn ifs(options: &Options) -> Result<(), ChaosError> {
+    let ifs = match &options.config {
+        Some(path) => Ifs::parse_config(&read_file(path)?)
+            .map_err(|err| ChaosError::in_file(path)(ChaosError::Parse(err)))?,
+        None => Ifs::barnsley_fern(),
+    };
+    play_ifs(options, &ifs)
+}
+
+/// One of the fern species in `FERNS`, the classic Barnsley fern unless `--variant` picks another
+#[allow(unused)]
+fn barnsley_fern(options: &Options) -> Result<(), ChaosError> {
+    play_ifs(options, &Ifs::fern(options.variant.unwrap_or(Fern(0))))
+}
+
+fn play_ifs(options: &Options, ifs: &Ifs) -> Result<(), ChaosError> {
+    let weights = WeightedIndex::new(&ifs.weights).expect("Weights are checked when parsed");
+
+    // The attractor isn't known up front, so frame it with a short run of its own
+    let mut framing_rng = StdRng::seed_from_u64(0);
+    let mut point = Point::new(0.0, 0.0);
+    let frame: Vec<Point> = (0..1000)
+        .map(|_| {
+            point = ifs.transforms[weights.sample(&mut framing_rng)].apply(&point);
+            point
+        })
+        .collect();
+
+    let mut rng = options.rng();
+    let mut current_point = Point::new(0.0, 0.0);
+    let mut output = Output::new(options, &frame)?;
+    for _ in 0..options.iterations() {
+        if output.is_full() {
+            break;
+        }
+        output.emit(current_point, &[])?;
+        current_point = ifs.transforms[weights.sample(&mut rng)].apply(&current_point);
+    }
+    output.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-12;
+
+    #[test]
+    fn nflake_ratio_matches_known_values() {
+        // pentaflake: 1 / phi^2
+        assert!((nflake_ratio(5) - (3.0 - 5.0_f64.sqrt()) / 2.0).abs() < EPSILON);
+        // hexaflake
+        assert!((nflake_ratio(6) - 1.0 / 3.0).abs() < EPSILON);
+        // heptaflake
+        assert!((nflake_ratio(7) - 0.307_978_528_369_904_1).abs() < EPSILON);
+        // octaflake: 1 / (2 + sqrt(2))
+        assert!((nflake_ratio(8) - 1.0 / (2.0 + 2.0_f64.sqrt())).abs() < EPSILON);
+    }
+
+    #[test]
+    fn nflake_ratio_is_half_for_triangles_and_squares() {
+        assert!((nflake_ratio(3) - 0.5).abs() < EPSILON);
+        assert!((nflake_ratio(4) - 0.5).abs() < EPSILON);
+    }
+
+    fn assert_point_eq(actual: Point, expected: Point) {
+        assert!(
+            (actual.x - expected.x).abs() < EPSILON && (actual.y - expected.y).abs() < EPSILON,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn distance_is_root_of_distance_squared() {
+        let a = Point::new(-1.0, 2.0);
+        let b = Point::new(2.0, -2.0);
+        assert!((a.distance_squared(&b) - 25.0).abs() < EPSILON);
+        assert!((a.distance(&b) - 5.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn jump_towards_rotated_by_zero_is_jump_towards() {
+        let from = Point::new(-3.0, 7.5);
+        let to = Point::new(12.0, -4.0);
+        for distance in &[0.0, 0.25, 0.5, 2.0 / 3.0, 1.0] {
+            assert_point_eq(
+                from.jump_towards_rotated(&to, *distance, 0.0),
+                from.jump_towards(&to, *distance),
+            );
+        }
+    }
+
+    #[test]
+    fn jump_towards_rotated_turns_around_target() {
+        let from = Point::new(3.0, 1.0);
+        let to = Point::new(1.0, 1.0);
+        let rotated = from.jump_towards_rotated(&to, 0.5, std::f64::consts::FRAC_PI_2);
+        assert_point_eq(rotated, Point::new(1.0, 2.0));
+    }
+
+    #[test]
+    fn convex_hull_drops_interior_and_collinear_points() {
+        let points = [
+            Point::new(1.0, 1.0),
+            Point::new(-1.0, -1.0),
+            Point::new(0.0, 0.0),
+            Point::new(1.0, -1.0),
+            Point::new(0.0, -1.0),
+            Point::new(-1.0, 1.0),
+            Point::new(0.5, -0.25),
+            Point::new(1.0, 1.0),
+        ];
+        assert_eq!(
+            convex_hull(&points),
+            vec![
+                Point::new(-1.0, -1.0),
+                Point::new(1.0, -1.0),
+                Point::new(1.0, 1.0),
+                Point::new(-1.0, 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn convex_hull_of_too_few_points_is_the_points() {
+        let points = [Point::new(2.0, 0.0), Point::new(-2.0, 0.0)];
+        assert_eq!(
+            convex_hull(&points),
+            vec![Point::new(-2.0, 0.0), Point::new(2.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn bounds_of_centered_polygon_are_negative() {
+        let bounds = RegularPolygon::centered(4, 1.0).bounds();
+        assert_point_eq(bounds.min, Point::new(-1.0, -1.0));
+        assert_point_eq(bounds.max, Point::new(1.0, 1.0));
+        assert!((bounds.width() - 2.0).abs() < EPSILON);
+        assert!((bounds.height() - 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn normalize_maps_negative_bounds_to_unit_square() {
+        let bounds = RegularPolygon::centered(4, 1.0).bounds();
+        assert_point_eq(bounds.normalize(&bounds.min), Point::new(0.0, 0.0));
+        assert_point_eq(bounds.normalize(&bounds.max), Point::new(1.0, 1.0));
+        assert_point_eq(
+            bounds.normalize(&Point::new(0.0, 0.0)),
+            Point::new(0.5, 0.5),
+        );
+        assert_point_eq(
+            bounds.normalize(&Point::new(-0.5, 0.5)),
+            Point::new(0.25, 0.75),
+        );
+    }
+
+    #[test]
+    fn cell_maps_negative_coordinates_into_grid() {
+        let bounds = Bounds::new(Point::new(-2.0, -1.0), Point::new(2.0, 1.0));
+        assert_eq!(bounds.cell(&Point::new(-2.0, -1.0), 4, 2), (0, 0));
+        assert_eq!(bounds.cell(&Point::new(2.0, 1.0), 4, 2), (3, 1));
+        assert_eq!(bounds.cell(&Point::new(-0.1, 0.1), 4, 2), (1, 1));
+        assert_eq!(bounds.cell(&Point::new(0.1, -0.1), 4, 2), (2, 0));
+        assert_eq!(bounds.cell(&Point::new(-5.0, 5.0), 4, 2), (0, 1));
+    }
+
+    #[test]
+    fn append_concatenates_runs() {
+        let path = std::env::temp_dir().join(format!("chaos-game-append-{}", std::process::id()));
+        let options = Options {
+            output: Some(path.to_string_lossy().into_owned()),
+            append: true,
+            ..Options::default()
+        };
+        std::fs::remove_file(&path).ok();
+
+        for run in &["first", "second"] {
+            let mut output = Output::new(&options, &[]).unwrap();
+            output.emit_row(format!("{} 1", run)).unwrap();
+            output.emit_row(format!("{} 2", run)).unwrap();
+            output.finish().unwrap();
+        }
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(contents, "first 1\nfirst 2\nsecond 1\nsecond 2\n");
+    }
+
+    #[test]
+    fn output_truncates_without_append() {
+        let path = std::env::temp_dir().join(format!("chaos-game-truncate-{}", std::process::id()));
+        let options = Options {
+            output: Some(path.to_string_lossy().into_owned()),
+            ..Options::default()
+        };
+
+        for run in &["first", "second"] {
+            let mut output = Output::new(&options, &[]).unwrap();
+            output.emit_row(run.to_string()).unwrap();
+            output.finish().unwrap();
+        }
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(contents, "second\n");
+    }
+
+    /// Runs a fractal the way `main` would, with its output sent to a file, and reads the points
+    /// back
+    fn generate_points(name: &str, seed: u64) -> Vec<Point> {
+        let path = std::env::temp_dir().join(format!(
+            "chaos-game-{}-{}-{}",
+            name,
+            seed,
+            std::process::id()
+        ));
+        let options = Options {
+            iterations: Some(1000),
+            seed: Some(seed),
+            output: Some(path.to_string_lossy().into_owned()),
+            ..Options::default()
+        };
+        find_fractal(&fractals(), name)
+            .unwrap()
+            .run(&options)
+            .unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        contents
+            .lines()
+            .map(|line| {
+                let mut coordinates = line.split(' ').map(|value| value.parse().unwrap());
+                Point::new(coordinates.next().unwrap(), coordinates.next().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_generates_same_points() {
+        for fractal in fractals() {
+            let first = generate_points(fractal.name(), 7);
+            let second = generate_points(fractal.name(), 7);
+            assert_eq!(first.len(), 1000);
+            assert_eq!(first, second, "{} isn't reproducible", fractal.name());
+        }
+    }
+
+    #[test]
+    fn different_seeds_generate_different_points() {
+        for fractal in fractals() {
+            let first = generate_points(fractal.name(), 7);
+            let second = generate_points(fractal.name(), 8);
+            // The starting point comes from the seed too, so even the first point can differ
+            assert_ne!(first, second, "{} ignores the seed", fractal.name());
+        }
+    }
+
+    #[test]
+    fn auto_iterations_grow_with_the_vertices() {
+        assert_eq!(auto_iterations(3), ITERATIONS);
+        assert_eq!(auto_iterations(6), 4 * ITERATIONS);
+        assert!(auto_iterations(4) < auto_iterations(5));
+    }
+
+    #[test]
+    fn composite_outline_goes_around_every_part() {
+        let shape = CompositePolygon(vec![
+            Box::new(Quadrilateral::square(100.0)),
+            Box::new(RegularPolygon::new(3, 40.0, Point::new(50.0, 50.0))),
+        ]);
+        assert_eq!(shape.outline(), Quadrilateral::square(100.0).points());
+        assert!(!polygon_contains(&shape.points(), &Point::new(5.0, 95.0)));
+        assert!(polygon_contains(&shape.outline(), &Point::new(5.0, 95.0)));
+    }
+
+    #[test]
+    fn generic_polygon_plays_like_the_triangle() {
+        let triangle = Triangle::new_equilateral(100.0);
+        let generic = GenericPolygon::new(triangle.points());
+        let points = triangle.points();
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let from_triangle = triangle.run(&mut rng, 1000, 0.5, &points, &mut |rng| {
+            rng.gen_range(0, points.len())
+        });
+        let mut rng = StdRng::seed_from_u64(5);
+        let from_generic = generic.run(&mut rng, 1000, 0.5, &points, &mut |rng| {
+            rng.gen_range(0, points.len())
+        });
+        assert_eq!(from_triangle.len(), 1000);
+        assert_eq!(from_triangle, from_generic);
+    }
+
+    #[test]
+    fn run_with_same_seed_starts_and_plays_the_same() {
+        let triangle = Triangle::new_equilateral(100.0);
+        let points = triangle.points();
+        let play = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            triangle.run(&mut rng, 1000, 0.5, &points, &mut |rng| {
+                rng.gen_range(0, points.len())
+            })
+        };
+        assert_eq!(play(3), play(3));
+        assert_ne!(play(3), play(4));
+    }
+
+    #[test]
+    fn sierpinski_triangle_occupies_expected_cells() {
+        // A right triangle lines its sub-triangles up with the grid, so at depth k the occupied
+        // cells of a 2^k by 2^k grid are exactly those whose column and row share no set bits
+        let triangle = Triangle::new(
+            Point::new(0.0, 0.0),
+            Point::new(100.0, 0.0),
+            Point::new(0.0, 100.0),
+        );
+        let vertices = triangle.points();
+        let mut rng = StdRng::seed_from_u64(119);
+        let iterations = 100_000;
+        let points = triangle.run(&mut rng, iterations, 0.5, &vertices, &mut |rng| {
+            rng.gen_range(0, vertices.len())
+        });
+
+        let size = 8;
+        let bounds = triangle.bounds();
+        let mut counts = std::collections::HashMap::new();
+        for point in &points {
+            *counts.entry(bounds.cell(point, size, size)).or_insert(0) += 1;
+        }
+
+        // Each expected cell should get about 1/27 of the points. Points can land exactly on a
+        // corner shared with an empty cell, so only count cells with a real share as occupied.
+        let threshold = iterations / 200;
+        let mut occupied = 0;
+        for column in 0..size {
+            for row in 0..size {
+                let count = counts.get(&(column, row)).copied().unwrap_or(0);
+                let expected = column & row == 0;
+                assert_eq!(
+                    count > threshold,
+                    expected,
+                    "cell ({}, {}) has {} points",
+                    column,
+                    row,
+                    count
+                );
+                occupied += (count > threshold) as usize;
+            }
+        }
+        assert_eq!(occupied, 27);
+
+        // Three quadrants share the points evenly and the fourth is empty
+        for (column, row) in &[(0, 0), (1, 0), (0, 1)] {
+            let quadrant = points
+                .iter()
+                .filter(|point| bounds.cell(point, 2, 2) == (*column, *row))
+                .count() as f64;
+            assert!((quadrant / iterations as f64 - 1.0 / 3.0).abs() < 0.01);
+        }
+        assert!(points
+            .iter()
+            .all(|point| point.x + point.y <= 100.0 + EPSILON));
+    }
+
+    #[test]
+    fn split_deals_rows_round_robin() {
+        let base = std::env::temp_dir().join(format!("chaos-game-split-{}", std::process::id()));
+        let base = base.to_string_lossy().into_owned();
+        let options = Options {
+            output: Some(base.clone()),
+            split: Some(2),
+            ..Options::default()
+        };
+
+        let mut output = Output::new(&options, &[]).unwrap();
+        for row in 0..5 {
+            output.emit_row(row.to_string()).unwrap();
+        }
+        output.finish().unwrap();
+
+        let read = |suffix| {
+            let path = format!("{}.{}", base, suffix);
+            let contents = std::fs::read_to_string(&path).unwrap();
+            std::fs::remove_file(&path).ok();
+            contents
+        };
+        assert_eq!(read("000"), "0\n2\n4\n");
+        assert_eq!(read("001"), "1\n3\n");
+    }
+
+    fn assert_point3_eq(actual: Point3, expected: Point3) {
+        assert!(
+            (actual.x - expected.x).abs() < EPSILON
+                && (actual.y - expected.y).abs() < EPSILON
+                && (actual.z - expected.z).abs() < EPSILON,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn rotate_x_maps_basis_vectors() {
+        let quarter = std::f64::consts::FRAC_PI_2;
+        let x = Point3::new(1.0, 0.0, 0.0);
+        let y = Point3::new(0.0, 1.0, 0.0);
+        let z = Point3::new(0.0, 0.0, 1.0);
+        assert_point3_eq(x.rotate_x(quarter), x);
+        assert_point3_eq(y.rotate_x(quarter), z);
+        assert_point3_eq(z.rotate_x(quarter), Point3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn rotate_y_maps_basis_vectors() {
+        let quarter = std::f64::consts::FRAC_PI_2;
+        let x = Point3::new(1.0, 0.0, 0.0);
+        let y = Point3::new(0.0, 1.0, 0.0);
+        let z = Point3::new(0.0, 0.0, 1.0);
+        assert_point3_eq(x.rotate_y(quarter), Point3::new(0.0, 0.0, -1.0));
+        assert_point3_eq(y.rotate_y(quarter), y);
+        assert_point3_eq(z.rotate_y(quarter), x);
+    }
+
+    #[test]
+    fn rotate_z_maps_basis_vectors() {
+        let quarter = std::f64::consts::FRAC_PI_2;
+        let x = Point3::new(1.0, 0.0, 0.0);
+        let y = Point3::new(0.0, 1.0, 0.0);
+        let z = Point3::new(0.0, 0.0, 1.0);
+        assert_point3_eq(x.rotate_z(quarter), y);
+        assert_point3_eq(y.rotate_z(quarter), Point3::new(-1.0, 0.0, 0.0));
+        assert_point3_eq(z.rotate_z(quarter), z);
+    }
+
+    #[test]
+    fn affine_transform_applies_linear_part_then_offset() {
+        let transform = AffineTransform::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0);
+        assert_point_eq(
+            transform.apply(&Point::new(1.0, -1.0)),
+            Point::new(4.0, 5.0),
+        );
+    }
+
+    #[test]
+    fn ifs_config_parses_transforms_and_weights() {
+        let ifs = Ifs::parse_config(
+            "# sierpinski\n\ntransform = 0.5, 0, 0, 0.5, 0, 0, 1\ntransform = 0.5,0,0,0.5,0.5,0,2\n",
+        )
+        .unwrap();
+        assert_eq!(
+            ifs.transforms,
+            vec![
+                AffineTransform::new(0.5, 0.0, 0.0, 0.5, 0.0, 0.0),
+                AffineTransform::new(0.5, 0.0, 0.0, 0.5, 0.5, 0.0),
+            ]
+        );
+        assert_eq!(ifs.weights, vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn ifs_config_rejects_bad_transforms() {
+        assert!(Ifs::parse_config("transform = 1, 2, 3").is_err());
+        assert!(Ifs::parse_config("transform = 1, 2, 3, 4, 5, x, 1").is_err());
+        assert!(Ifs::parse_config("rotate = 1, 0, 0, 1, 0, 0, 1").is_err());
+        assert!(Ifs::parse_config("transform = 1, 0, 0, 1, 0, 0, -1").is_err());
+        assert!(Ifs::parse_config("transform = 1, 0, 0, 1, 0, 0, 0").is_err());
+        assert!(Ifs::parse_config("# nothing").is_err());
+    }
+
+    #[test]
+    fn parse_points_skips_comments_and_keeps_rows() {
+        let points = parse_points("# trajectory\n1 2 7\n\n-0.5 3\n").unwrap();
+        assert_eq!(
+            points,
+            vec![
+                (Point::new(1.0, 2.0), "1 2 7"),
+                (Point::new(-0.5, 3.0), "-0.5 3")
+            ]
+        );
+        assert!(parse_points("1 2\n3\n").is_err());
+        assert!(parse_points("1 x\n").is_err());
+    }
+
+    #[test]
+    fn dedup_points_drops_points_within_epsilon_of_kept_ones() {
+        let points = [
+            Point::new(0.0, 0.0),
+            Point::new(0.05, 0.0),
+            Point::new(0.2, 0.0),
+            Point::new(0.0, 0.0),
+            Point::new(-0.09, -0.0),
+            Point::new(0.3, 0.3),
+            Point::new(-0.0, 0.0),
+        ];
+        assert_eq!(dedup_points(&points, 0.1), vec![0, 2, 5]);
+        assert_eq!(dedup_points(&points, 0.0), vec![0, 1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn svg_attributes_are_escaped() {
+        assert_eq!(escape_attribute("#222222"), "#222222");
+        assert_eq!(
+            escape_attribute(r#""/><x a='&'"#),
+            "&quot;/&gt;&lt;x a=&apos;&amp;&apos;"
+        );
+    }
+
+    #[test]
+    fn rgb_packs_and_blends() {
+        assert_eq!(Rgb(0x12, 0x34, 0x56).packed(), 0x123456);
+        let (black, white) = (Rgb(0, 0, 0), Rgb(255, 255, 255));
+        assert_eq!(black.blend(&white, 0.0), black);
+        assert_eq!(black.blend(&white, 1.0), white);
+        assert_eq!(black.blend(&white, 0.5), Rgb(128, 128, 128));
+        assert_eq!(black.blend(&white, 2.0), white);
+    }
+
+    #[test]
+    fn color_schemes_follow_their_inputs() {
+        let options = Options {
+            iterations: Some(11),
+            ..Options::default()
+        };
+        let frame = Quadrilateral::square(2.0).points();
+        let origin = Point::new(0.0, 0.0);
+
+        let vertex = ColorScheme::Vertex.colorize(&options, &frame);
+        assert_ne!(
+            vertex.color(&origin, 0, Some(0)),
+            vertex.color(&origin, 0, Some(1))
+        );
+        assert_eq!(
+            vertex.color(&origin, 0, Some(1)),
+            vertex.color(&origin, 5, Some(9))
+        );
+
+        let iteration = ColorScheme::Iteration.colorize(&options, &frame);
+        assert_eq!(iteration.color(&origin, 0, None), Rgb(0x44, 0x01, 0x54));
+        assert_eq!(iteration.color(&origin, 10, None), Rgb(0xfd, 0xe7, 0x25));
+
+        let radius = ColorScheme::Radius.colorize(&options, &frame);
+        assert_eq!(
+            radius.color(&Point::new(1.0, 1.0), 0, None),
+            Rgb(0x44, 0x01, 0x54)
+        );
+        assert_eq!(radius.color(&origin, 0, None), Rgb(0xfd, 0xe7, 0x25));
+
+        let grayscale = Options {
+            color_palette: Palette::Grayscale,
+            ..options
+        };
+        let iteration = ColorScheme::Iteration.colorize(&grayscale, &frame);
+        assert_eq!(iteration.color(&origin, 5, None), Rgb(128, 128, 128));
+    }
+
+    #[test]
+    fn palettes_blend_between_their_stops() {
+        assert_eq!(Palette::Rainbow.at(0.0), Rgb(255, 0, 0));
+        assert_eq!(Palette::Rainbow.at(0.1), Rgb(255, 128, 0));
+        assert_eq!(Palette::Rainbow.at(1.0), Rgb(255, 0, 255));
+        assert_eq!(Palette::Magma.at(-1.0), Rgb(0x00, 0x00, 0x04));
+        assert_eq!(Palette::Magma.at(2.0), Rgb(0xfc, 0xfd, 0xbf));
+        assert_eq!(Palette::Grayscale.at(0.25), Rgb(64, 64, 64));
+        assert_eq!("viridis".parse(), Ok(Palette::Viridis));
+        assert!("jet".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn quantized_colors_are_palette_indices() {
+        assert_eq!(
+            Palette::Grayscale.quantized(3),
+            vec![Rgb(0, 0, 0), Rgb(128, 128, 128), Rgb(255, 255, 255)]
+        );
+        assert_eq!(Palette::Magma.quantized(1), vec![Rgb(0x00, 0x00, 0x04)]);
+
+        let options = Options {
+            iterations: Some(11),
+            quantize_colors: Some(3),
+            ..Options::default()
+        };
+        let frame = Quadrilateral::square(2.0).points();
+        let origin = Point::new(0.0, 0.0);
+        let iteration = ColorScheme::Iteration.colorize(&options, &frame);
+        let levels: Vec<f64> = [0, 2, 3, 7, 10]
+            .iter()
+            .map(|&i| iteration.column(&origin, i, None))
+            .collect();
+        assert_eq!(levels, vec![0.0, 0.0, 1.0, 1.0, 2.0]);
+        let vertex = ColorScheme::Vertex.colorize(&options, &frame);
+        assert_eq!(vertex.column(&origin, 0, None), -1.0);
+        assert_eq!(vertex.column(&origin, 0, Some(3)), 2.0);
+    }
+
+    #[test]
+    fn p2_quantile_is_exact_for_a_few_values() {
+        let mut median = P2Quantile::new(0.5);
+        assert!(median.estimate().is_nan());
+        for value in &[3.0, 1.0, 2.0] {
+            median.add(*value);
+        }
+        assert_eq!(median.estimate(), 2.0);
+    }
+
+    #[test]
+    fn p2_quantile_estimates_quartiles_of_a_stream() {
+        let mut values: Vec<f64> = (0..10001).map(f64::from).collect();
+        values.shuffle(&mut StdRng::seed_from_u64(3));
+        let mut stats = CoordinateStats::new();
+        for value in values {
+            stats.add(value);
+        }
+        assert_eq!((stats.min, stats.max), (0.0, 10000.0));
+        for (quartile, expected) in stats.quartiles.iter().zip(&[2500.0, 5000.0, 7500.0]) {
+            assert!(
+                (quartile.estimate() - expected).abs() < 100.0,
+                "{} != {}",
+                quartile.estimate(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn fraction_parses_decimals_and_ratios() {
+        assert_eq!("0.25".parse(), Ok(Fraction(0.25)));
+        assert_eq!("2/3".parse(), Ok(Fraction(2.0 / 3.0)));
+        assert_eq!("1 / 4".parse(), Ok(Fraction(0.25)));
+        assert!("1/0".parse::<Fraction>().is_err());
+        assert!("1/x".parse::<Fraction>().is_err());
+        assert!("two".parse::<Fraction>().is_err());
+    }
+
+    #[test]
+    fn jump_distance_must_be_between_0_and_1() {
+        for valid in &[0.5, 2.0 / 3.0, 1e-9, 0.999] {
+            assert_eq!(Fraction(*valid).jump_distance(false), Ok(*valid));
+        }
+        for invalid in &[0.0, -0.5, 1.0, 1.5, f64::NAN, f64::INFINITY] {
+            assert!(
+                Fraction(*invalid).jump_distance(false).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn allow_extrapolation_permits_jumping_past_the_target() {
+        assert_eq!(Fraction(1.0).jump_distance(true), Ok(1.0));
+        assert_eq!(Fraction(1.5).jump_distance(true), Ok(1.5));
+        for invalid in &[0.0, -0.5, f64::NAN, f64::INFINITY] {
+            assert!(
+                Fraction(*invalid).jump_distance(true).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn jump_towards_is_lerp() {
+        let from = Point::new(-3.0, 7.5);
+        let to = Point::new(12.0, -4.0);
+        assert_point_eq(from.lerp(&to, 0.0), from);
+        assert_point_eq(from.lerp(&to, 1.0), to);
+        assert_point_eq(from.lerp(&to, 0.5), from.midpoint(&to));
+        for t in &[-0.5, 0.0, 0.25, 2.0 / 3.0, 1.0, 1.5] {
+            assert_eq!(from.jump_towards(&to, *t), from.lerp(&to, *t));
+        }
+    }
+
+    #[test]
+    fn chain_seeds_differ_between_chains_and_masters() {
+        let seeds: std::collections::HashSet<u64> = (0..4)
+            .flat_map(|master| (0..8).map(move |chain| chain_seed(master, chain)))
+            .collect();
+        assert_eq!(seeds.len(), 32);
+        assert_eq!(chain_seed(7, 3), chain_seed(7, 3));
+    }
+
+    #[test]
+    fn threads_with_same_seed_generate_same_points() {
+        let run = |name: &str| {
+            let path = std::env::temp_dir().join(format!(
+                "chaos-game-threads-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let options = Options {
+                iterations: Some(1001),
+                seed: Some(11),
+                threads: Some(3),
+                output: Some(path.to_string_lossy().into_owned()),
+                ..Options::default()
+            };
+            generate(find_fractal(&fractals(), "vicsek").unwrap(), &options).unwrap();
+            let contents = std::fs::read_to_string(&path).unwrap();
+            std::fs::remove_file(&path).ok();
+            contents
+        };
+        let first = run("first");
+        assert_eq!(first.lines().count(), 1001);
+        assert_eq!(first, run("second"));
+    }
+
+    #[test]
+    fn estimate_scales_with_rows_and_columns() {
+        let options = Options {
+            iterations: Some(1000),
+            ..Options::default()
+        };
+        let estimate = Estimate::of(&options);
+        assert_eq!(estimate.rows, 1000);
+        assert_eq!(estimate.output_bytes, 1000 * 2 * (BYTES_PER_VALUE + 1));
+        assert_eq!(estimate.memory_bytes, 0);
+
+        let colored = Estimate::of(&Options {
+            value_column: Some(ValueColumn::Index),
+            tile: Some((2, 3)),
+            max_points: Some(4000),
+            ..options.clone()
+        });
+        assert_eq!(colored.rows, 4000);
+        assert_eq!(colored.output_bytes, 4000 * 4 * (BYTES_PER_VALUE + 1));
+
+        let hull = Estimate::of(&Options {
+            hull: true,
+            ..options
+        });
+        assert_eq!(hull.rows, 0);
+        assert_eq!(hull.memory_bytes, 1000 * std::mem::size_of::<Point>());
+    }
+
+    #[test]
+    fn human_bytes_picks_a_unit() {
+        assert_eq!(human_bytes(999), "999 B");
+        assert_eq!(human_bytes(1500), "1.5 KB");
+        assert_eq!(human_bytes(38_000_000), "38.0 MB");
+    }
+
+    #[test]
+    fn start_point_follows_weights() {
+        let square = Quadrilateral::square(2.0);
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..20 {
+            let start = square.start_point(&mut rng, Some(&[0.0, 0.0, 1.0, 0.0]));
+            assert_eq!(start, square.medial_points()[2]);
+        }
+        let starts: std::collections::HashSet<_> = (0..50)
+            .map(|_| square.start_point(&mut rng, None))
+            .map(|point| (point.x.to_bits(), point.y.to_bits()))
+            .collect();
+        assert_eq!(starts.len(), 4);
+    }
+
+    #[test]
+    fn augmented_target_set_alternates_vertices_and_midpoints() {
+        let square = Quadrilateral::square(2.0);
+        let augmented = TargetSet::Augmented.points(&square);
+        assert_eq!(augmented.len(), 8);
+        for (i, vertex) in square.points().iter().enumerate() {
+            assert_eq!(augmented[2 * i], *vertex);
+            assert_eq!(augmented[2 * i + 1], square.medial_points()[i]);
+        }
+        assert_eq!(TargetSet::Vertices.points(&square), square.points());
+        assert_eq!(TargetSet::Medial.points(&square), square.medial_points());
+    }
+
+    #[test]
+    fn complex_sqrt_squares_back() {
+        for &(x, y) in &[
+            (4.0, 0.0),
+            (-4.0, 0.0),
+            (3.0, 4.0),
+            (-0.5, -1.25),
+            (0.0, 2.0),
+        ] {
+            let root = Point::new(x, y).complex_sqrt();
+            assert!(root.x >= 0.0);
+            let square = Point::new(root.x * root.x - root.y * root.y, 2.0 * root.x * root.y);
+            assert_point_eq(square, Point::new(x, y));
+        }
+    }
+
+    #[test]
+    fn polygon_contains_counts_edges_as_inside() {
+        // An L shape, so a ray can cross it more than once
+        let polygon = [
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 2.0),
+            Point::new(0.0, 2.0),
+        ];
+        assert!(polygon_contains(&polygon, &Point::new(0.5, 0.5)));
+        assert!(polygon_contains(&polygon, &Point::new(0.5, 1.5)));
+        assert!(polygon_contains(&polygon, &Point::new(1.5, 0.5)));
+        assert!(!polygon_contains(&polygon, &Point::new(1.5, 1.5)));
+        assert!(!polygon_contains(&polygon, &Point::new(-0.5, 0.5)));
+        assert!(!polygon_contains(&polygon, &Point::new(2.5, 0.5)));
+        for edge_point in &[
+            Point::new(1.0, 0.0),
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.5),
+            Point::new(1.0, 1.5),
+            Point::new(0.0, 1.0),
+        ] {
+            assert!(polygon_contains(&polygon, edge_point), "{:?}", edge_point);
+        }
+    }
+
+    #[test]
+    fn flags_win_over_environment_over_defaults() {
+        let env = |name: &str| match name {
+            "CHAOS_ITERATIONS" => Some("42".to_string()),
+            "CHAOS_JUMP_DISTANCE" => Some("1/3".to_string()),
+            _ => None,
+        };
+        let from_env = Options::parse(vec![]).unwrap().with_env(env).unwrap();
+        assert_eq!(from_env.iterations(), 42);
+        assert_eq!(from_env.jump_distance, Some(vec![Fraction(1.0 / 3.0)]));
+
+        let args = vec!["--iterations".to_string(), "7".to_string()];
+        assert_eq!(
+            Options::parse(args)
+                .unwrap()
+                .with_env(env)
+                .unwrap()
+                .iterations(),
+            7
+        );
+        assert_eq!(
+            Options::parse(vec![])
+                .unwrap()
+                .with_env(|_| None)
+                .unwrap()
+                .iterations(),
+            ITERATIONS
+        );
+    }
+
+    /// Hands out a fixed sequence of numbers so a test decides every random choice
+    struct ScriptedRng {
+        values: Vec<u64>,
+        next: usize,
+    }
+
+    impl ScriptedRng {
+        /// Each entry is `(index, count)`, making `gen_range(0, count)` or `choose` on `count` items
+        /// return `index`
+        fn picking(choices: &[(usize, usize)]) -> Self {
+            // A quarter of the way into the slice of u64s that `gen_range` maps to `index`, early
+            // enough to stay clear of the values it rejects to avoid bias
+            let value = |&(index, count): &(usize, usize)| {
+                (((4 * index + 1) as u128) << 64) / (4 * count) as u128
+            };
+            Self {
+                values: choices.iter().map(|choice| value(choice) as u64).collect(),
+                next: 0,
+            }
+        }
+    }
+
+    impl rand::RngCore for ScriptedRng {
+        fn next_u32(&mut self) -> u32 {
+            // The high bits, so 32 bit ranges pick the same index
+            (self.next_u64() >> 32) as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let value = *self
+                .values
+                .get(self.next)
+                .expect("Ran out of scripted values");
+            self.next += 1;
+            value
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            fill_from_next_u64(self, dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            fill_from_next_u64(self, dest);
+            Ok(())
+        }
+    }
+
+    fn fill_from_next_u64(rng: &mut ScriptedRng, dest: &mut [u8]) {
+        use rand::RngCore;
+        for chunk in dest.chunks_mut(8) {
+            let bytes = rng.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    #[test]
+    fn scripted_rng_decides_the_start_and_every_vertex() {
+        let triangle = Triangle::new_equilateral(4.0);
+        let points = triangle.points();
+        let medial = triangle.medial_points();
+        // Start from the second medial point, then jump to vertices 2, 0, 0, 1. The game picks a
+        // target after the last point too, which goes unused.
+        let mut rng = ScriptedRng::picking(&[(1, 3), (2, 3), (0, 3), (0, 3), (1, 3), (0, 3)]);
+        let played = triangle.run(&mut rng, 5, 0.5, &points, &mut |rng| {
+            rng.gen_range(0, points.len())
+        });
+
+        let mut expected = vec![medial[1]];
+        for vertex in &[2, 0, 0, 1] {
+            let last = *expected.last().unwrap();
+            expected.push(last.midpoint(&points[*vertex]));
+        }
+        assert_eq!(played, expected);
+    }
+
+    #[test]
+    fn scripted_rng_drives_a_restriction_policy() {
+        let square = Quadrilateral::square(2.0);
+        let points = square.points();
+        // Vertex 1 comes up twice in a row, so the no repeat rule draws again and gets 3
+        let mut rng = ScriptedRng::picking(&[(0, 4), (1, 4), (1, 4), (3, 4), (2, 4), (0, 4)]);
+        let mut previous = None;
+        let mut chosen = Vec::new();
+        square.play(
+            &mut rng,
+            4,
+            0.5,
+            &points,
+            &mut |rng| loop {
+                let vertex = rng.gen_range(0, points.len());
+                if Some(vertex) != previous {
+                    previous = Some(vertex);
+                    break vertex;
+                }
+            },
+            &mut |_, target| {
+                chosen.extend(target);
+                true
+            },
+        );
+        assert_eq!(chosen, vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn no_repeat_draws_again_on_the_previous_vertex() {
+        let mut rng = ScriptedRng::picking(&[(2, 3), (2, 3), (0, 3), (0, 3), (0, 3), (1, 3)]);
+        let mut no_repeat = NoRepeat { previous: None };
+        let picks: Vec<usize> = (0..3).map(|_| no_repeat.pick(&mut rng, 3)).collect();
+        assert_eq!(picks, vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn composite_polygon_concatenates_its_parts() {
+        let square = Quadrilateral::square(2.0);
+        let triangle = Triangle::new_equilateral(1.0);
+        let composite = CompositePolygon(vec![Box::new(square), Box::new(triangle)]);
+
+        let mut points = square.points();
+        points.extend(triangle.points());
+        assert_eq!(composite.points(), points);
+
+        let mut medial = square.medial_points();
+        medial.extend(triangle.medial_points());
+        assert_eq!(composite.medial_points(), medial);
+    }
+
+    /// Tracks the bytes each thread has allocated and not yet freed, and the most it's had at once,
+    /// so a test can check how much memory a run holds on to
+    struct TrackingAllocator;
+
+    thread_local! {
+        static LIVE_BYTES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+        static PEAK_BYTES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    unsafe impl std::alloc::GlobalAlloc for TrackingAllocator {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            // `try_with` since the thread locals may already be gone while a thread shuts down
+            let _ = LIVE_BYTES.try_with(|live| {
+                live.set(live.get() + layout.size());
+                let _ = PEAK_BYTES.try_with(|peak| peak.set(peak.get().max(live.get())));
+            });
+            std::alloc::System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            let _ = LIVE_BYTES.try_with(|live| live.set(live.get().saturating_sub(layout.size())));
+            std::alloc::System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+    /// The most memory held at once on this thread while generating `iterations` points to a file
+    fn peak_bytes_generating(iterations: usize) -> usize {
+        let path = std::env::temp_dir().join(format!(
+            "chaos-game-streaming-{}-{}",
+            iterations,
+            std::process::id()
+        ));
+        let options = Options {
+            iterations: Some(iterations),
+            seed: Some(1),
+            output: Some(path.to_string_lossy().into_owned()),
+            ..Options::default()
+        };
+        let fractals = fractals();
+        let fractal = find_fractal(&fractals, "sierpinski-triangle").unwrap();
+
+        let baseline = LIVE_BYTES.with(|live| live.get());
+        PEAK_BYTES.with(|peak| peak.set(baseline));
+        fractal.run(&options).unwrap();
+        let peak = PEAK_BYTES.with(|peak| peak.get()) - baseline;
+        std::fs::remove_file(&path).ok();
+        peak
+    }
+
+    #[test]
+    fn streaming_memory_does_not_grow_with_iterations() {
+        let small = peak_bytes_generating(1_000);
+        let large = peak_bytes_generating(100_000);
+        assert!(
+            large <= small + 1024,
+            "{} bytes for 1000 points but {} for 100000",
+            small,
+            large
+        );
+    }
+
+    #[test]
+    fn ferns_are_picked_by_name_or_number() {
+        assert_eq!("barnsley".parse(), Ok(Fern(0)));
+        assert_eq!("2".parse(), Ok(Fern(2)));
+        assert_eq!("leptosporangiate".parse(), Ok(Fern(2)));
+        assert!("3".parse::<Fern>().is_err());
+        assert_eq!(Ifs::fern(Fern(0)), Ifs::barnsley_fern());
+        for (i, (_, table)) in FERNS.iter().enumerate() {
+            let total: f64 = table.iter().map(|row| row[6]).sum();
+            assert!(
+                (total - 1.0).abs() < 1e-9,
+                "fern {} weights add up to {}",
+                i,
+                total
+            );
+        }
+    }
+
+    #[test]
+    fn pascal_rows_have_two_to_the_ones_odd_entries() {
+        let rows: Vec<Vec<usize>> = pascal_odd_entries(64).collect();
+        assert_eq!(rows[0], vec![0]);
+        assert_eq!(rows[4], vec![0, 4]);
+        assert_eq!(rows[5], vec![0, 1, 4, 5]);
+        for (row, odd) in rows.iter().enumerate() {
+            assert_eq!(odd.len(), 1 << row.count_ones(), "row {}", row);
+        }
+    }
+
+    #[test]
+    fn first_power_of_two_rows_have_power_of_three_odd_entries() {
+        for n in 0..8 {
+            let odd: usize = pascal_odd_entries(1 << n).map(|row| row.len()).sum();
+            assert_eq!(odd, 3_usize.pow(n));
+        }
+    }
+
+    #[test]
+    fn transforms_apply_in_a_fixed_order() {
+        let frame = [
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+            Point::new(0.0, 2.0),
+        ];
+        let options = Options {
+            rotate_output: Some(90.0),
+            invert_y: true,
+            affine: Some(AffineTransform::new(2.0, 0.0, 0.0, 1.0, 10.0, 0.0)),
+            ..Options::default()
+        };
+        let pipeline = TransformPipeline::new(&options, &frame);
+        assert_eq!(pipeline.0.len(), 3);
+        // Rotated around (1, 1) to (1, 2), flipped to (1, 0), then stretched and moved to (12, 0)
+        let point = pipeline.apply(Point::new(2.0, 1.0));
+        assert!(
+            point.distance(&Point::new(12.0, 0.0)) < 1e-12,
+            "{:?}",
+            point
+        );
+    }
+
+    #[test]
+    fn no_transforms_leave_points_alone() {
+        let pipeline = TransformPipeline::new(&Options::default(), &[Point::new(1.0, 1.0)]);
+        assert_eq!(pipeline, TransformPipeline::default());
+        assert_eq!(pipeline.apply(Point::new(3.0, 4.0)), Point::new(3.0, 4.0));
+    }
+
+    #[test]
+    fn reflections_mirror_across_the_centroid() {
+        let center = Point::new(1.0, 2.0);
+        let point = Point::new(4.0, 3.0);
+        let x = Transform::Reflect {
+            axis: Axis::X,
+            center,
+        };
+        let y = Transform::Reflect {
+            axis: Axis::Y,
+            center,
+        };
+        assert_eq!(x.apply(point), Point::new(4.0, 1.0));
+        assert_eq!(y.apply(point), Point::new(-2.0, 3.0));
+        assert_eq!(x.apply(x.apply(point)), point);
+    }
+
+    #[test]
+    fn from_polar_inverts_atan2_and_hypot() {
+        let point = Point::from_polar(2.0, 3.0 * std::f64::consts::FRAC_PI_4);
+        assert!((point.x.hypot(point.y) - 2.0).abs() < 1e-12);
+        assert!((point.y.atan2(point.x) - 3.0 * std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn roses_close_after_the_turns_their_k_needs() {
+        let pi = std::f64::consts::PI;
+        assert_eq!(rose_turn(3.0), pi);
+        assert_eq!(rose_turn(4.0), 2.0 * pi);
+        assert_eq!(rose_turn(1.5), 4.0 * pi);
+        assert_eq!(rose_turn(1.0 / 3.0), 3.0 * pi);
+        assert_eq!(rose_turn(0.4), 10.0 * pi);
+        assert_eq!(rose_turn(-3.0), pi);
+        // r = cos(1.5 theta) only comes back to its start heading the same way after two turns
+        let r = |theta: f64| (1.5 * theta).cos();
+        let at = |theta: f64| Point::from_polar(r(theta), theta);
+        assert!(at(0.0).distance(&at(2.0 * pi)) > 1.0);
+        assert!(at(0.0).distance(&at(4.0 * pi)) < 1e-12);
+        // r = cos(3 theta) is back where it started after half a turn
+        let r = |theta: f64| (3.0 * theta).cos();
+        let start = Point::from_polar(r(0.0), 0.0);
+        assert!(start.distance(&Point::from_polar(r(pi), pi)) < 1e-12);
+    }
+
+    #[test]
+    fn significant_figures_keep_relative_detail() {
+        assert_eq!(significant_figures(123.456, 3), "123");
+        assert_eq!(significant_figures(0.000123456, 3), "0.000123");
+        assert_eq!(significant_figures(12345.0, 3), "12300");
+        assert_eq!(significant_figures(61725.0, 2), "62000");
+        assert_eq!(significant_figures(-1.5, 4), "-1.500");
+        assert_eq!(significant_figures(9.99, 2), "10");
+        assert_eq!(significant_figures(0.0, 3), "0");
+        assert_eq!(significant_figures(1.23456e-7, 3), "1.23e-7");
+        assert_eq!(significant_figures(1.5e20, 2), "1.5e20");
+    }
+
+    #[test]
+    fn apollonian_gasket_starts_from_the_known_curvatures() {
+        let mut curvatures: Vec<f64> = apollonian_circles(0)
+            .iter()
+            .map(|circle| circle.curvature.round())
+            .collect();
+        curvatures.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            curvatures,
+            vec![-1.0, 2.0, 2.0, 3.0, 3.0, 6.0, 6.0, 6.0, 6.0, 15.0, 15.0]
+        );
+    }
+
+    #[test]
+    fn apollonian_circles_nest_inside_the_outer_circle() {
+        let depth = 4;
+        let circles = apollonian_circles(depth);
+        // Five to start, then six gaps each growing a ternary tree of depth + 1 levels
+        assert_eq!(
+            circles.len(),
+            5 + 6 * (3_usize.pow(depth as u32 + 1) - 1) / 2
+        );
+        let origin = Point::new(0.0, 0.0);
+        for circle in &circles[1..] {
+            let reach = circle.center.distance(&origin) + circle.radius();
+            assert!(reach <= 1.0 + 1e-9, "{:?} pokes out", circle);
+            // Curvatures of an integral gasket stay whole numbers
+            assert!((circle.curvature - circle.curvature.round()).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn morton_codes_interleave_x_and_y() {
+        assert_eq!(spread_bits(0b1011), 0b1000101);
+        assert_eq!(spread_bits(u32::MAX), 0x5555_5555_5555_5555);
+
+        let bounds = Bounds::of(&[Point::new(0.0, 0.0), Point::new(1.0, 1.0)]);
+        let code = |x, y| morton_code(&bounds, &Point::new(x, y));
+        assert_eq!(code(0.0, 0.0), 0);
+        assert_eq!(code(1.0, 1.0), u64::MAX);
+        // The curve visits the bottom left, bottom right, top left, then top right quadrant
+        let quadrants = [
+            code(0.25, 0.25),
+            code(0.75, 0.25),
+            code(0.25, 0.75),
+            code(0.75, 0.75),
+        ];
+        assert!(quadrants.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn hilbert_curve_visits_every_cell_a_step_at_a_time() {
+        for order in 1..=6 {
+            let cells: Vec<(u64, u64)> = (0..1 << (2 * order))
+                .map(|d| hilbert_point(order, d))
+                .collect();
+            assert_eq!(cells.len(), 4_usize.pow(order));
+            let unique: std::collections::HashSet<_> = cells.iter().collect();
+            assert_eq!(unique.len(), cells.len());
+            for pair in cells.windows(2) {
+                let (dx, dy) = (pair[0].0.abs_diff(pair[1].0), pair[0].1.abs_diff(pair[1].1));
+                assert_eq!(dx + dy, 1, "order {} jumps from {:?}", order, pair);
+            }
+        }
+        assert_eq!(hilbert_point(1, 0), (0, 0));
+        assert_eq!(hilbert_point(1, 3), (1, 0));
+    }
+
+    #[test]
+    fn start_angle_turns_where_the_vertices_begin() {
+        let center = Point::new(50.0, 50.0);
+        let right = RegularPolygon::new(5, 10.0, center);
+        let up = right.with_start_angle(std::f64::consts::FRAC_PI_2);
+        assert!(right.points()[0].distance(&Point::new(60.0, 50.0)) < 1e-9);
+        assert!(up.points()[0].distance(&Point::new(50.0, 60.0)) < 1e-9);
+        for (turned, point) in up.points().iter().zip(right.points()) {
+            let expected = point
+                .translate(-center.x, -center.y)
+                .rotate(std::f64::consts::FRAC_PI_2)
+                .translate(center.x, center.y);
+            assert!(turned.distance(&expected) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn nearest_neighbor_distances_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(170);
+        let points: Vec<Point> = (0..500)
+            .map(|_| Point::new(rng.gen_range(0.0, 100.0), rng.gen_range(0.0, 30.0)))
+            .collect();
+        let distances = nearest_neighbor_distances(&points);
+        for (i, point) in points.iter().enumerate() {
+            let nearest = points
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, other)| point.distance(other))
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(distances[i], nearest);
+        }
+    }
+
+    #[test]
+    fn diverging_points_are_an_error_with_non_finite_abort() {
+        let path = std::env::temp_dir().join(format!("chaos-game-abort-{}", std::process::id()));
+        let options = Options {
+            output: Some(path.to_string_lossy().into_owned()),
+            non_finite: NonFinite::Abort,
+            ..Options::default()
+        };
+        let mut output = Output::new(&options, &[]).unwrap();
+        output.emit(Point::new(1.0, 2.0), &[]).unwrap();
+        let err = output.emit(Point::new(f64::NAN, 2.0), &[]).unwrap_err();
+        assert!(err.to_string().contains("diverged"), "{}", err);
+        // What came before is flushed out
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(contents, "1 2\n");
+    }
+
+    #[test]
+    fn temp_files_are_fresh_and_removed_when_dropped() {
+        let first = TempFile::new("test").unwrap();
+        let second = TempFile::new("test").unwrap();
+        assert_ne!(first.path, second.path);
+        assert!(std::path::Path::new(&first.path).exists());
+        let path = first.path.clone();
+        drop(first);
+        assert!(!std::path::Path::new(&path).exists());
+        assert!(std::path::Path::new(&second.path).exists());
+    }
+
+    #[test]
+    fn prior_points_find_the_nearest_earlier_point() {
+        let mut rng = StdRng::seed_from_u64(105);
+        let bounds = Bounds::of(&[Point::new(0.0, 0.0), Point::new(100.0, 100.0)]);
+        let mut prior = PriorPoints::new(bounds, 300);
+        assert_eq!(prior.nearest(&Point::new(50.0, 50.0)), f64::INFINITY);
+        let mut points = vec![Point::new(-40.0, 250.0)];
+        points.extend(
+            (0..300).map(|_| Point::new(rng.gen_range(0.0, 100.0), rng.gen_range(0.0, 100.0))),
+        );
+        for (i, point) in points.iter().enumerate() {
+            if i > 0 {
+                let nearest = points[..i]
+                    .iter()
+                    .map(|other| point.distance(other))
+                    .fold(f64::INFINITY, f64::min);
+                assert_eq!(prior.nearest(point), nearest);
+            }
+            prior.insert(*point);
+        }
+    }
+
+    #[test]
+    fn nearest_neighbors_on_a_grid_are_a_spacing_apart() {
+        let points: Vec<Point> = (0..100)
+            .map(|i| Point::new((i % 10) as f64 * 3.0, (i / 10) as f64 * 3.0))
+            .collect();
+        assert!(nearest_neighbor_distances(&points)
+            .iter()
+            .all(|distance| *distance == 3.0));
+        assert!(nearest_neighbor_distances(&points[..1]).is_empty());
+    }
+
+    #[test]
+    fn coverage_counts_each_cell_once() {
+        let bounds = Bounds::of(&[Point::new(0.0, 0.0), Point::new(1.0, 1.0)]);
+        let mut coverage = Coverage::new(
+            bounds,
+            COVERAGE_GRID,
+            2.0 / (COVERAGE_GRID * COVERAGE_GRID) as f64,
+        );
+        coverage.add(&Point::new(0.0, 0.0));
+        coverage.add(&Point::new(0.001, 0.001));
+        assert_eq!(coverage.count, 1);
+        assert!(!coverage.reached());
+        coverage.add(&Point::new(1.0, 1.0));
+        assert_eq!(coverage.count, 2);
+        assert!(coverage.reached());
+    }
+
+    #[test]
+    fn histogram_binary_matrix_leads_with_the_columns_then_each_row() {
+        let bounds = Bounds::new(Point::new(0.0, 0.0), Point::new(2.0, 2.0));
+        let mut histogram = Histogram::new(bounds, 2, true);
+        for point in &[
+            Point::new(0.5, 0.5),
+            Point::new(1.5, 0.5),
+            Point::new(1.5, 0.2),
+        ] {
+            histogram.add(point);
+        }
+        histogram.add(&Point::new(0.1, 1.9));
+        let bytes = histogram.binary_matrix();
+        let values: Vec<f32> = bytes
+            .chunks(4)
+            .map(|chunk| f32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        assert_eq!(values, vec![2.0, 0.5, 1.5, 0.5, 1.0, 2.0, 1.5, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn adaptive_decimation_keeps_sparse_cells_and_thins_dense_ones() {
+        let bounds = Bounds::new(Point::new(0.0, 0.0), Point::new(1.0, 1.0));
+        let mut decimation = AdaptiveDecimation::new(bounds, 10, StdRng::seed_from_u64(179));
+        let sparse = (0..DECIMATE_GRID)
+            .map(|i| Point::new((i as f64 + 0.5) / DECIMATE_GRID as f64, 0.9))
+            .filter(|point| decimation.keeps(point))
+            .count();
+        assert_eq!(sparse, DECIMATE_GRID);
+        let dense = (0..10000)
+            .filter(|_| decimation.keeps(&Point::new(0.5, 0.5)))
+            .count();
+        // About 10(1 + ln 1000)
+        assert!((50..100).contains(&dense), "{}", dense);
+    }
+
+    #[test]
+    fn regular_square_has_its_vertices_on_the_axes() {
+        let square = RegularPolygon::centered(4, 1.0).points();
+        let expected = [
+            Point::new(1.0, 0.0),
+            Point::new(0.0, 1.0),
+            Point::new(-1.0, 0.0),
+            Point::new(0.0, -1.0),
+        ];
+        assert_eq!(square.len(), expected.len());
+        for (actual, expected) in square.iter().zip(&expected) {
+            assert_point_eq(*actual, *expected);
+        }
+    }
+
+    #[test]
+    fn regular_polygon_sides_are_equal_and_in_order() {
+        for sides in 3..=12 {
+            let polygon = RegularPolygon::new(sides, 7.0, Point::new(3.0, -2.0));
+            let points = polygon.points();
+            let side = 2.0 * 7.0 * (std::f64::consts::PI / sides as f64).sin();
+            let apothem = 7.0 * (std::f64::consts::PI / sides as f64).cos();
+            for (i, point) in points.iter().enumerate() {
+                let next = points[(i + 1) % sides];
+                assert!(
+                    (point.distance(&next) - side).abs() < 1e-9,
+                    "{} sides",
+                    sides
+                );
+            }
+            // Adjacent vertices come next to each other, so the medial points are edge centers
+            let medial = polygon.medial_points();
+            for (i, midpoint) in medial.iter().enumerate() {
+                let next = points[(i + 1) % sides];
+                assert!(midpoint.distance(&points[i].midpoint(&next)) < 1e-9);
+                assert!((midpoint.distance(&polygon.center) - apothem).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn regular_triangle_is_equilateral() {
+        let triangle = RegularPolygon::centered(3, 2.0).points();
+        let side = 2.0 * 3.0_f64.sqrt();
+        for (a, b) in &[(0, 1), (1, 2), (2, 0)] {
+            assert!((triangle[*a].distance(&triangle[*b]) - side).abs() < EPSILON);
+        }
+        assert_point_eq(Point::centroid(&triangle), Point::new(0.0, 0.0));
+        assert_point_eq(triangle[0], Point::new(2.0, 0.0));
+        assert_point_eq(triangle[1], Point::new(-1.0, 3.0_f64.sqrt()));
+    }
+
+    #[test]
+    fn medial_polygons_halve_in_size_each_level() {
+        let square = Quadrilateral::square(8.0).points();
+        let levels = medial_polygons(square.clone(), 2);
+        assert_eq!(levels.len(), 3);
+        assert_eq!(levels[0].points, square);
+        // A square's medial polygon is the square turned 45 degrees with half the area
+        let area_and_width = |polygon: &GenericPolygon| {
+            let area = polygon.points[0].distance_squared(&polygon.points[1]);
+            (area, polygon.bounds().width())
+        };
+        assert_eq!(area_and_width(&levels[0]), (64.0, 8.0));
+        assert_eq!(area_and_width(&levels[1]), (32.0, 8.0));
+        assert_eq!(area_and_width(&levels[2]), (16.0, 4.0));
+    }
+
+    #[test]
+    fn penrose_edges_are_all_one_length_shrinking_by_the_golden_ratio() {
+        let phi = (1.0 + 5.0_f64.sqrt()) / 2.0;
+        for depth in 0..5 {
+            let edges = penrose_edges(depth);
+            let length = phi.powi(-(depth as i32));
+            for (from, to) in &edges {
+                assert!((from.distance(to) - length).abs() < 1e-9, "depth {}", depth);
+            }
+        }
+        assert_eq!(penrose_edges(0).len(), 10);
+        // A thick half splits into three and a thin one into two, so the thick ones catch up to
+        // phi times as many
+        let mut triangles = vec![RobinsonTriangle {
+            thick: false,
+            a: Point::new(0.0, 0.0),
+            b: Point::from_polar(1.0, -0.1 * std::f64::consts::PI),
+            c: Point::from_polar(1.0, 0.1 * std::f64::consts::PI),
+        }];
+        for _ in 0..12 {
+            triangles = triangles
+                .iter()
+                .flat_map(RobinsonTriangle::subdivide)
+                .collect();
+        }
+        let thick = triangles.iter().filter(|triangle| triangle.thick).count();
+        let ratio = thick as f64 / (triangles.len() - thick) as f64;
+        assert!((ratio - phi).abs() < 1e-3, "{}", ratio);
+    }
+
+    #[test]
+    fn jump_distances_cycle_one_per_jump() {
+        let triangle = Triangle::new_equilateral(100.0);
+        let mut rng = StdRng::seed_from_u64(184);
+        let mut xs = Vec::new();
+        triangle
+            .play_towards(
+                &mut rng,
+                Point::new(64.0, 0.0),
+                5,
+                &JumpDistances::Cycle(vec![0.5, 0.25]),
+                &mut |_, _| (Point::new(0.0, 0.0), None),
+                &mut |point, _| {
+                    xs.push(point.x);
+                    Ok::<_, ChaosError>(true)
+                },
+            )
+            .unwrap();
+        assert_eq!(xs, vec![64.0, 32.0, 24.0, 12.0, 9.0]);
+
+        let options =
+            Options::parse(vec!["--jump-distance".to_string(), "1/2,0.4".to_string()]).unwrap();
+        assert_eq!(options.jump_distances(0.9).distances(), [0.5, 0.4]);
+        assert_eq!(Options::default().jump_distances(0.9).distances(), [0.9]);
+    }
+
+    #[test]
+    fn weighted_jump_distances_follow_their_weights() {
+        let args = vec![
+            "--jump-distances".to_string(),
+            "1/2:0.7,0.4:0.3".to_string(),
+        ];
+        let options = Options::parse(args).unwrap();
+        assert_eq!(
+            options.weighted_jumps,
+            Some(vec![
+                WeightedJump {
+                    distance: Fraction(0.5),
+                    weight: 0.7
+                },
+                WeightedJump {
+                    distance: Fraction(0.4),
+                    weight: 0.3
+                },
+            ])
+        );
+        let jump_distances = options.jump_distances(0.9);
+        assert_eq!(jump_distances.distances(), [0.5, 0.4]);
+
+        let mut rng = StdRng::seed_from_u64(199);
+        let halves = (0..10_000)
+            .filter(|&i| jump_distances.next(&mut rng, i) == 0.5)
+            .count();
+        assert!((6800..7200).contains(&halves), "{}", halves);
+        assert!("0.5".parse::<WeightedJump>().is_err());
+        assert!("0.5:x".parse::<WeightedJump>().is_err());
+    }
+
+    #[test]
+    fn reproducing_command_adds_the_resolved_values_and_quotes() {
+        let args: Vec<String> = ["vicsek", "--print-command", "--restrict-expr", "c != p"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let options = Options {
+            seed: Some(12),
+            jump_distance: Some(vec![Fraction(0.5), Fraction(0.25)]),
+            ..Options::parse(args.clone()).unwrap()
+        };
+        assert_eq!(
+            reproducing_command(&args, &options),
+            format!(
+                "chaos-game vicsek --restrict-expr 'c != p' --iterations {} \
+                 --jump-distance 0.5,0.25 --seed 12",
+                ITERATIONS
+            )
+        );
+
+        let args: Vec<String> = ["--seed", "3", "--iterations", "10"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let options = Options::parse(args.clone()).unwrap();
+        assert_eq!(
+            reproducing_command(&args, &options),
+            "chaos-game --seed 3 --iterations 10"
+        );
+        let quoted = vec!["--output".to_string(), "it's here".to_string()];
+        assert!(reproducing_command(&quoted, &options).contains(r"'it'\''s here'"));
+    }
+
+    #[test]
+    fn chaos_errors_say_where_they_came_from() {
+        let number: ChaosError = "x".parse::<f64>().unwrap_err().into();
+        assert_eq!(number.to_string(), "invalid float literal");
+        let in_file = ChaosError::in_file("shape.svg")(ChaosError::Invalid("no <path>".into()));
+        assert_eq!(in_file.to_string(), "shape.svg: no <path>");
+
+        let missing = read_file("/no/such/file").unwrap_err();
+        assert!(
+            missing.to_string().starts_with("/no/such/file: "),
+            "{}",
+            missing
+        );
+        let source = std::error::Error::source(&missing).unwrap();
+        assert!(source.downcast_ref::<ChaosError>().is_some());
+    }
+
+    #[test]
+    fn last_point_reads_the_final_row() {
+        let path = std::env::temp_dir().join(format!("chaos-game-last-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let rows: String = (0..1000).map(|i| format!("{} {} 7\n", i, -i)).collect();
+        std::fs::write(path, rows + "\n# done\n").unwrap();
+        assert_eq!(last_point(path).unwrap(), Point::new(999.0, -999.0));
+
+        std::fs::write(path, "# nothing yet\n").unwrap();
+        assert!(last_point(path).is_err());
+        std::fs::write(path, "1 x\n").unwrap();
+        assert!(last_point(path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
 }
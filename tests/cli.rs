@@ -0,0 +1,1193 @@
+use std::process::{Command, Output};
+
+fn chaos_game(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_chaos-game"))
+        .args(args)
+        .output()
+        .expect("Couldn't run chaos-game")
+}
+
+fn stdout(output: Output) -> String {
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn generates_one_row_of_two_numbers_per_iteration() {
+    let points = stdout(chaos_game(&[
+        "sierpinski-triangle",
+        "--iterations",
+        "100",
+        "--seed",
+        "3",
+        "--quiet",
+    ]));
+    assert_eq!(points.lines().count(), 100);
+    for line in points.lines() {
+        let values: Vec<f64> = line
+            .split(' ')
+            .map(|value| value.parse().unwrap_or_else(|_| panic!("{}", line)))
+            .collect();
+        assert_eq!(values.len(), 2, "{}", line);
+    }
+}
+
+#[test]
+fn seed_makes_runs_repeatable() {
+    let run = |seed| {
+        stdout(chaos_game(&[
+            "vicsek",
+            "--iterations",
+            "50",
+            "--seed",
+            seed,
+        ]))
+    };
+    assert_eq!(run("9"), run("9"));
+    assert_ne!(run("9"), run("10"));
+}
+
+#[test]
+fn every_keeps_every_nth_point() {
+    let run = |extra: &[&str]| {
+        let mut args = vec!["--iterations", "100", "--seed", "4"];
+        args.extend(extra);
+        stdout(chaos_game(&args))
+    };
+    let all = run(&[]);
+    let every = run(&["--every", "10"]);
+    let expected: Vec<&str> = all.lines().step_by(10).collect();
+    assert_eq!(every.lines().collect::<Vec<_>>(), expected);
+
+    let convergence = run(&["--convergence"]);
+    let thinned = run(&["--convergence", "--every", "10"]);
+    let expected: Vec<&str> = convergence
+        .lines()
+        .filter(|line| line.split(' ').next().unwrap().parse::<usize>().unwrap() % 10 == 0)
+        .collect();
+    assert_eq!(expected.len(), 9);
+    assert_eq!(thinned.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn summary_only_prints_stats_instead_of_points() {
+    let summary = stdout(chaos_game(&[
+        "--iterations",
+        "1000",
+        "--seed",
+        "4",
+        "--summary-only",
+    ]));
+    let lines: Vec<&str> = summary.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", summary);
+    assert_eq!(lines[0], "points: 1000");
+    assert!(lines[1].starts_with("x: min "), "{}", summary);
+    assert!(lines[2].starts_with("y: min "), "{}", summary);
+}
+
+#[test]
+fn scale_and_integer_round_the_scaled_coordinates() {
+    let run = |extra: &[&str]| {
+        let mut args = vec!["--iterations", "50", "--seed", "5", "--quiet"];
+        args.extend(extra);
+        stdout(chaos_game(&args))
+    };
+    let floats = run(&[]);
+    let integers = run(&["--scale", "10", "--integer"]);
+    for (float, integer) in floats.lines().zip(integers.lines()) {
+        let expected: Vec<i64> = float
+            .split(' ')
+            .map(|value| (value.parse::<f64>().unwrap() * 10.0).round() as i64)
+            .collect();
+        let actual: Vec<i64> = integer.split(' ').map(|v| v.parse().unwrap()).collect();
+        assert_eq!(actual, expected);
+    }
+}
+
+#[test]
+fn rotate_output_turns_points_around_the_centroid() {
+    let run = |extra: &[&str]| {
+        let mut args = vec!["square-one", "--iterations", "50", "--seed", "6", "--quiet"];
+        args.extend(extra);
+        stdout(chaos_game(&args))
+    };
+    let points = run(&[]);
+    let rotated = run(&["--rotate-output", "90"]);
+    for (point, rotated) in points.lines().zip(rotated.lines()) {
+        let point: Vec<f64> = point.split(' ').map(|v| v.parse().unwrap()).collect();
+        let rotated: Vec<f64> = rotated.split(' ').map(|v| v.parse().unwrap()).collect();
+        // A quarter turn around the middle of the 100 wide square
+        let expected = [100.0 - point[1], point[0]];
+        assert!((rotated[0] - expected[0]).abs() < 1e-9, "{:?}", rotated);
+        assert!((rotated[1] - expected[1]).abs() < 1e-9, "{:?}", rotated);
+    }
+}
+
+#[test]
+fn emit_selection_traces_square_one_never_repeating() {
+    let path = std::env::temp_dir().join(format!("chaos-game-selection-{}", std::process::id()));
+    stdout(chaos_game(&[
+        "square-one",
+        "--iterations",
+        "500",
+        "--seed",
+        "8",
+        "--quiet",
+        "--emit-selection",
+        path.to_str().unwrap(),
+    ]));
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    let vertices: Vec<usize> = trace.lines().map(|line| line.parse().unwrap()).collect();
+    // Every point but the starting one was reached by jumping towards a vertex
+    assert_eq!(vertices.len(), 499);
+    assert!(vertices.iter().all(|&vertex| vertex < 4));
+    assert!(vertices.windows(2).all(|pair| pair[0] != pair[1]));
+}
+
+#[test]
+fn triangle_no_repeat_never_picks_a_vertex_twice_in_a_row() {
+    let path = std::env::temp_dir().join(format!("chaos-game-no-repeat-{}", std::process::id()));
+    stdout(chaos_game(&[
+        "sierpinski-triangle-no-repeat",
+        "--iterations",
+        "500",
+        "--emit-selection",
+        path.to_str().unwrap(),
+    ]));
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    let vertices: Vec<usize> = trace.lines().map(|line| line.parse().unwrap()).collect();
+    assert_eq!(vertices.len(), 499);
+    assert!(vertices.iter().all(|&vertex| vertex < 3));
+    assert!(vertices.windows(2).all(|pair| pair[0] != pair[1]));
+}
+
+#[test]
+fn contains_answers_whether_a_point_is_inside_the_polygon() {
+    let inside = chaos_game(&["square-one", "--contains", "50,50"]);
+    assert!(inside.status.success());
+    assert_eq!(String::from_utf8_lossy(&inside.stdout), "yes\n");
+
+    let outside = chaos_game(&["square-one", "--contains", "150,50"]);
+    assert_eq!(outside.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&outside.stdout), "no\n");
+    assert!(!String::from_utf8_lossy(&outside.stderr).contains("error"));
+    // Inside the square but outside the triangle in it
+    let composite = chaos_game(&["triangle-in-square", "--contains", "5,95"]);
+    assert_eq!(String::from_utf8_lossy(&composite.stdout), "yes\n");
+}
+
+#[test]
+fn out_of_range_jump_distances_exit_with_an_error() {
+    for jump in &["0", "-1/2", "1", "3/2", "NaN"] {
+        let output = chaos_game(&["--iterations", "10", "--jump-distance", jump]);
+        assert_eq!(output.status.code(), Some(1), "{}", jump);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("--jump-distance"), "{}", stderr);
+    }
+
+    let extrapolated = chaos_game(&[
+        "--iterations",
+        "10",
+        "--jump-distance",
+        "3/2",
+        "--allow-extrapolation",
+    ]);
+    assert_eq!(stdout(extrapolated).lines().count(), 10);
+}
+
+#[test]
+fn weighted_jump_distances_need_positive_weights() {
+    for jumps in &["1/2:0", "1/2:-1,0.4:1", "2:1", "1/2:NaN"] {
+        let output = chaos_game(&["--iterations", "10", "--jump-distances", jumps]);
+        assert_eq!(output.status.code(), Some(1), "{}", jumps);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("--jump-distances"), "{}", stderr);
+    }
+
+    let both = chaos_game(&["--jump-distance", "1/2", "--jump-distances", "1/2:1"]);
+    assert_eq!(both.status.code(), Some(1));
+
+    let weighted = chaos_game(&["--iterations", "10", "--jump-distances", "1/2:0.7,0.4:0.3"]);
+    assert_eq!(stdout(weighted).lines().count(), 10);
+}
+
+#[test]
+fn clip_to_polygon_clips_to_the_outline() {
+    let output = chaos_game(&[
+        "triangle-in-square",
+        "--iterations",
+        "2000",
+        "--clip-to-polygon",
+        "--stats",
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(stderr.contains("clipped: 0 points"), "{}", stderr);
+    assert_eq!(stdout(output).lines().count(), 2000);
+
+    let output = chaos_game(&["julia", "--clip-to-polygon"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn meta_describes_the_run_in_a_separate_file() {
+    let path = std::env::temp_dir().join(format!("chaos-game-meta-{}.json", std::process::id()));
+    let output = chaos_game(&[
+        "square-one",
+        "--iterations",
+        "5",
+        "--seed",
+        "7",
+        "--meta",
+        path.to_str().unwrap(),
+    ]);
+    assert_eq!(stdout(output).lines().count(), 5);
+    let meta = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    for field in &[
+        "\"fractal\": \"square-one\"",
+        "\"vertices\": [[0, 0], [100, 0], [100, 100], [0, 100]]",
+        "\"bounds\": {\"min\": [0, 0], \"max\": [100, 100]}",
+        "\"jump_distance\": 0.5",
+        "\"seed\": 7",
+        "\"iterations\": 5",
+        "\"target_set\": null",
+        "\"restriction\": \"c != p\"",
+    ] {
+        assert!(meta.contains(field), "{} not in {}", field, meta);
+    }
+
+    let output = chaos_game(&[
+        "square-one",
+        "--iterations",
+        "5",
+        "--restrict-expr",
+        "(c - p) % n != 2",
+        "--meta",
+        path.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    let meta = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert!(
+        meta.contains("\"restriction\": \"((c - p) % n) != 2\""),
+        "{}",
+        meta
+    );
+}
+
+#[test]
+fn verify_checks_the_holes_stay_empty() {
+    for fractal in &["sierpinski-triangle", "vicsek"] {
+        let output = chaos_game(&[fractal, "--iterations", "20000", "--verify"]);
+        assert!(output.status.success(), "{:?}", output);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("verify: passed"), "{}", stderr);
+    }
+
+    // Jumping less than halfway overlaps the copies and fills in the middle
+    let overlapping = chaos_game(&[
+        "sierpinski-triangle",
+        "--iterations",
+        "20000",
+        "--jump-distance",
+        "0.4",
+        "--verify",
+    ]);
+    assert_eq!(overlapping.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&overlapping.stderr);
+    assert!(stderr.contains("verify: failed"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupting_writes_out_the_points_so_far() {
+    let path = std::env::temp_dir().join(format!("chaos-game-interrupt-{}", std::process::id()));
+    let child = Command::new(env!("CARGO_BIN_EXE_chaos-game"))
+        .args(["--iterations", "1000000000000", "--output"])
+        .arg(&path)
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Couldn't run chaos-game");
+
+    // Wait for the first block of points to be written so the game is well underway
+    let started = std::time::Instant::now();
+    while std::fs::metadata(&path).map_or(0, |file| file.len()) == 0 {
+        assert!(started.elapsed().as_secs() < 10, "nothing was written");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGINT);
+    }
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(130));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("interrupted"), "{}", stderr);
+    let points = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert!(points.ends_with('\n'));
+    assert!(points.lines().all(|line| line
+        .split(' ')
+        .filter_map(|v| v.parse::<f64>().ok())
+        .count()
+        == 2));
+}
+
+#[test]
+fn until_coverage_stops_once_enough_of_the_grid_is_filled() {
+    let coverage = chaos_game(&["square-one", "--seed", "4", "--until-coverage", "0.1"]);
+    let points = stdout(coverage).lines().count();
+    assert!(points > 0 && points < 1000000, "{} points", points);
+
+    // A fraction the fractal can't reach stops at --iterations instead
+    let capped = chaos_game(&[
+        "sierpinski-triangle",
+        "--iterations",
+        "1000",
+        "--until-coverage",
+        "1",
+    ]);
+    assert_eq!(stdout(capped).lines().count(), 1000);
+}
+
+#[test]
+fn depth_shade_adds_a_column_from_0_to_1_following_z() {
+    let output = chaos_game(&[
+        "sierpinski-tetrahedron",
+        "--iterations",
+        "1000",
+        "--rotate-x",
+        "30",
+        "--depth-shade",
+    ]);
+    let rows: Vec<Vec<f64>> = stdout(output)
+        .lines()
+        .map(|line| {
+            line.split(' ')
+                .map(|value| value.parse().unwrap())
+                .collect()
+        })
+        .collect();
+    assert_eq!(rows.len(), 1000);
+    for row in &rows {
+        assert_eq!(row.len(), 4);
+        assert!((0.0..=1.0).contains(&row[3]), "{:?}", row);
+    }
+    // Higher points are shaded higher
+    let (low, high) = (&rows[0], &rows[999]);
+    assert_eq!(low[2] < high[2], low[3] < high[3]);
+}
+
+#[test]
+fn restrict_expr_filters_the_picked_targets() {
+    let path = std::env::temp_dir().join(format!("chaos-game-restrict-{}", std::process::id()));
+    let output = chaos_game(&[
+        "square-one",
+        "--iterations",
+        "2000",
+        "--restrict-expr",
+        "(c - p) % n != 2",
+        "--emit-selection",
+        path.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    let picks: Vec<i64> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| line.parse().unwrap())
+        .collect();
+    std::fs::remove_file(&path).ok();
+    assert!(picks
+        .windows(2)
+        .all(|pair| (pair[1] - pair[0]).rem_euclid(4) != 2));
+    // Repeats are allowed, unlike square-one's own rule
+    assert!(picks.windows(2).any(|pair| pair[0] == pair[1]));
+
+    let unsatisfiable = chaos_game(&["--iterations", "10", "--restrict-expr", "0"]);
+    assert_eq!(unsatisfiable.status.code(), Some(1));
+}
+
+#[test]
+fn rate_caps_the_rows_written_per_second() {
+    let started = std::time::Instant::now();
+    let output = chaos_game(&["--iterations", "50", "--rate", "200"]);
+    assert_eq!(stdout(output).lines().count(), 50);
+    // The 50th row is due a quarter of a second after the first
+    assert!(started.elapsed() >= std::time::Duration::from_millis(245));
+}
+
+#[test]
+fn histogram_counts_every_point_into_a_grid() {
+    let output = chaos_game(&["--seed", "3", "--iterations", "1000", "--histogram", "4"]);
+    let stdout = stdout(output);
+    let cells: Vec<u64> = stdout
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.split(' ').nth(2).unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(cells.len(), 16);
+    assert_eq!(cells.iter().sum::<u64>(), 1000);
+    assert_eq!(stdout.lines().filter(|line| line.is_empty()).count(), 4);
+
+    let output = chaos_game(&[
+        "--seed",
+        "3",
+        "--iterations",
+        "1000",
+        "--histogram",
+        "4",
+        "--histogram-binary",
+    ]);
+    assert_eq!(output.stdout.len(), 5 * 5 * 4);
+}
+
+#[test]
+fn unseeded_runs_log_a_seed_that_replays_them() {
+    let output = chaos_game(&["--iterations", "100"]);
+    let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+    let seed = stderr
+        .lines()
+        .find_map(|line| line.strip_prefix("seed: "))
+        .expect("no seed logged");
+    let replayed = chaos_game(&["--iterations", "100", "--seed", seed]);
+    assert!(!String::from_utf8_lossy(&replayed.stderr).contains("seed:"));
+    assert_eq!(stdout(output), stdout(replayed));
+}
+
+#[test]
+fn ellipse_attractor_stays_inside_its_ellipse() {
+    let points = stdout(chaos_game(&[
+        "ellipse-attractor",
+        "--seed",
+        "4",
+        "--iterations",
+        "2000",
+        "--a",
+        "20",
+        "--b",
+        "5",
+    ]));
+    let mut widest: f64 = 0.0;
+    for line in points.lines() {
+        let values: Vec<f64> = line.split(' ').map(|v| v.parse().unwrap()).collect();
+        let (x, y) = (values[0] / 20.0, values[1] / 5.0);
+        assert!(x * x + y * y < 1.0, "{}", line);
+        widest = widest.max(values[0].abs());
+    }
+    assert!(widest > 10.0);
+}
+
+#[test]
+fn delta_rows_sum_back_to_the_points() {
+    let run = |extra: &[&str]| {
+        let mut args = vec!["--seed", "8", "--iterations", "300", "--scale", "7.3"];
+        args.extend_from_slice(extra);
+        let rows: Vec<Vec<i64>> = stdout(chaos_game(&args))
+            .lines()
+            .map(|line| line.split(' ').map(|v| v.parse().unwrap()).collect())
+            .collect();
+        rows
+    };
+    let points = run(&["--integer"]);
+    let deltas = run(&["--integer", "--delta"]);
+    assert_eq!(deltas[0], points[0]);
+    let (mut x, mut y) = (0, 0);
+    for (delta, point) in deltas.iter().zip(&points) {
+        x += delta[0];
+        y += delta[1];
+        assert_eq!(vec![x, y], *point);
+    }
+}
+
+#[test]
+fn grid_unique_writes_one_point_per_cell() {
+    let points = stdout(chaos_game(&[
+        "--seed",
+        "2",
+        "--iterations",
+        "20000",
+        "--grid-unique",
+        "10",
+    ]));
+    let cells: Vec<(i64, i64)> = points
+        .lines()
+        .map(|line| {
+            let values: Vec<f64> = line.split(' ').map(|v| v.parse().unwrap()).collect();
+            (
+                (values[0] / 10.0).floor() as i64,
+                (values[1] / 10.0).floor() as i64,
+            )
+        })
+        .collect();
+    let unique: std::collections::HashSet<_> = cells.iter().collect();
+    assert_eq!(unique.len(), cells.len());
+    // The 100 wide triangle touches a few dozen of the 10 wide cells, well short of every point
+    assert!(cells.len() > 20 && cells.len() < 100, "{}", cells.len());
+}
+
+#[test]
+fn panels_lay_fractals_out_side_by_side() {
+    let points = stdout(chaos_game(&[
+        "--seed",
+        "5",
+        "--iterations",
+        "500",
+        "--panels",
+        "sierpinski-triangle,vicsek,julia",
+        "--cols",
+        "2",
+    ]));
+    let mut boxes = [(f64::MAX, f64::MIN, f64::MAX, f64::MIN); 3];
+    for line in points.lines() {
+        let values: Vec<f64> = line.split(' ').map(|v| v.parse().unwrap()).collect();
+        let panel = &mut boxes[values[2] as usize];
+        *panel = (
+            panel.0.min(values[0]),
+            panel.1.max(values[0]),
+            panel.2.min(values[1]),
+            panel.3.max(values[1]),
+        );
+    }
+    assert_eq!(points.lines().count(), 1500);
+    // Triangle then vicsek along the top, julia below the triangle
+    assert!(boxes[0].1 < boxes[1].0);
+    assert!(boxes[2].3 < boxes[0].2);
+    assert!(boxes[2].0 < boxes[1].0);
+
+    let output = chaos_game(&[
+        "--seed",
+        "5",
+        "--iterations",
+        "50",
+        "--panels",
+        "sierpinski-triangle,vicsek",
+        "--print-command",
+    ]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("chaos-game --seed 5 --iterations 50 --panels sierpinski-triangle,vicsek"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn threads_apply_output_flags_to_the_merged_rows() {
+    let run = |extra: &[&str]| {
+        let mut args = vec![
+            "vicsek",
+            "--seed",
+            "3",
+            "--iterations",
+            "1000",
+            "--threads",
+            "3",
+        ];
+        args.extend(extra);
+        stdout(chaos_game(&args))
+    };
+    assert_eq!(run(&["--max-points", "100"]).lines().count(), 100);
+    assert_eq!(run(&["--sample", "10"]).lines().count(), 10);
+    // Each chain of 334, 333, and 333 points keeps every fourth of its own
+    assert_eq!(run(&["--every", "4"]).lines().count(), 3 * 84);
+    let packed = run(&["--points-per-line", "4"]);
+    assert_eq!(packed.lines().count(), 250);
+    assert!(packed.lines().all(|line| line.split(' ').count() == 8));
+}
+
+#[test]
+fn cell_count_check_compares_occupied_cells_to_the_copies() {
+    let output = chaos_game(&[
+        "vicsek",
+        "--seed",
+        "6",
+        "--iterations",
+        "200000",
+        "--depth",
+        "3",
+    ]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("cells:"));
+    let output = chaos_game(&[
+        "vicsek",
+        "--seed",
+        "6",
+        "--iterations",
+        "200000",
+        "--depth",
+        "3",
+        "--cell-count-check",
+    ]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let line = stderr
+        .lines()
+        .find(|line| line.starts_with("cells:"))
+        .expect("no cell count reported");
+    assert!(
+        line.contains("of a 27 x 27 grid, 125 in theory (5^3)"),
+        "{}",
+        line
+    );
+    let ratio: f64 = line.rsplit(' ').next().unwrap().parse().unwrap();
+    assert!((ratio - 1.0).abs() < 0.05, "{}", line);
+    let output = chaos_game(&[
+        "vicsek",
+        "--iterations",
+        "1000",
+        "--depth",
+        "30",
+        "--cell-count-check",
+    ]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("in theory (5^30)"), "{}", stderr);
+}
+
+#[test]
+fn svg_polygon_plays_on_the_shape_in_the_file() {
+    let path = std::env::temp_dir().join(format!("chaos-game-shape-{}.svg", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">
+  <path fill="none" stroke="black" d="M 0,0 L 100,0 L 100,100 L 0,100 Z"/>
+</svg>
+"#,
+    )
+    .unwrap();
+    let points = stdout(chaos_game(&[
+        "svg-polygon",
+        "--svg-input",
+        path.to_str().unwrap(),
+        "--iterations",
+        "1000",
+        "--seed",
+        "1",
+    ]));
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(points.lines().count(), 1000);
+    for line in points.lines() {
+        let xy: Vec<f64> = line.split(' ').map(|v| v.parse().unwrap()).collect();
+        // SVG's y axis points down, so the square is flipped below the x axis
+        assert!((0.0..=100.0).contains(&xy[0]), "{}", line);
+        assert!((-100.0..=0.0).contains(&xy[1]), "{}", line);
+    }
+}
+
+#[test]
+fn with_hull_writes_the_hull_after_the_points() {
+    let output = stdout(chaos_game(&[
+        "sierpinski-triangle",
+        "--with-hull",
+        "--iterations",
+        "1000",
+        "--seed",
+        "1",
+    ]));
+    let (points, hull) = output.split_at(output.find("\n\n").unwrap() + 1);
+    let rows = |text: &str| -> Vec<Vec<f64>> {
+        text.lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.split(' ').map(|v| v.parse().unwrap()).collect())
+            .collect()
+    };
+    let (points, hull) = (rows(points), rows(hull));
+
+    assert_eq!(points.len(), 1000);
+    assert!(points.iter().all(|row| row.len() == 3 && row[2] == 0.0));
+    assert!(hull.len() >= 4, "{:?}", hull);
+    assert!(hull.iter().all(|row| row.len() == 3 && row[2] == 1.0));
+    assert_eq!(hull.first(), hull.last());
+    for vertex in &hull {
+        assert!(points.iter().any(|point| point[..2] == vertex[..2]));
+    }
+}
+
+#[test]
+fn resume_from_carries_on_from_the_last_point() {
+    let path = std::env::temp_dir().join(format!("chaos-game-resume-{}", std::process::id()));
+    let path = path.to_str().unwrap();
+    let run = |extra: &[&str]| {
+        let mut args = vec!["--iterations", "100", "--output", path];
+        args.extend_from_slice(extra);
+        assert!(chaos_game(&args).status.success());
+    };
+    run(&["--seed", "1"]);
+    run(&["--seed", "2", "--resume-from", path, "--append"]);
+    let contents = std::fs::read_to_string(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let points: Vec<(f64, f64)> = contents
+        .lines()
+        .map(|line| {
+            let mut values = line.split(' ').map(|v| v.parse::<f64>().unwrap());
+            (values.next().unwrap(), values.next().unwrap())
+        })
+        .collect();
+    assert_eq!(points.len(), 200);
+    // The first resumed point is a jump halfway from the last one to a vertex
+    let ((x, y), (next_x, next_y)) = (points[99], points[100]);
+    let vertex = (2.0 * next_x - x, 2.0 * next_y - y);
+    assert!(
+        [(0.0, 0.0), (100.0, 0.0), (50.0, 100.0)]
+            .iter()
+            .any(|&(vx, vy)| (vx - vertex.0).abs() < 1e-9 && (vy - vertex.1).abs() < 1e-9),
+        "{:?} -> {:?}",
+        points[99],
+        points[100]
+    );
+}
+
+#[test]
+fn quantize_colors_writes_palette_indices_after_a_palette_header() {
+    let output = stdout(chaos_game(&[
+        "--color",
+        "vertex",
+        "--color-palette",
+        "grayscale",
+        "--quantize-colors",
+        "3",
+        "--iterations",
+        "100",
+        "--seed",
+        "1",
+    ]));
+    let (header, rows): (Vec<&str>, Vec<&str>) =
+        output.lines().partition(|line| line.starts_with('#'));
+    assert_eq!(
+        header,
+        vec![
+            "# color 0 #000000",
+            "# color 1 #808080",
+            "# color 2 #ffffff"
+        ]
+    );
+
+    assert_eq!(rows.len(), 100);
+    assert!(rows[0].ends_with(" -1"), "{}", rows[0]);
+    for row in &rows[1..] {
+        let index = row.split(' ').nth(2).unwrap();
+        assert!(["0", "1", "2"].contains(&index), "{}", row);
+    }
+}
+
+#[test]
+fn selection_histogram_counts_each_target() {
+    let output = chaos_game(&[
+        "--selection-histogram",
+        "--iterations",
+        "3001",
+        "--seed",
+        "1",
+    ]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let counts: Vec<u64> = stderr
+        .lines()
+        .filter(|line| line.starts_with("target "))
+        .map(|line| line.split(' ').nth(2).unwrap().parse().unwrap())
+        .collect();
+
+    // Every point but the starting one was reached by picking a target
+    assert_eq!(counts.len(), 3, "{}", stderr);
+    assert_eq!(counts.iter().sum::<u64>(), 3000);
+    for count in counts {
+        assert!((900..1100).contains(&count), "{}", stderr);
+    }
+}
+
+#[test]
+fn output_streams_to_a_tcp_socket() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("tcp://{}", listener.local_addr().unwrap());
+    let reader = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = String::new();
+        std::io::Read::read_to_string(&mut stream, &mut received).unwrap();
+        received
+    });
+    let output = chaos_game(&["--iterations", "100", "--seed", "1", "--output", &address]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let received = reader.join().unwrap();
+    let expected = stdout(chaos_game(&["--iterations", "100", "--seed", "1"]));
+    assert_eq!(received, expected);
+}
+
+#[cfg(unix)]
+#[test]
+fn output_streams_to_a_unix_socket() {
+    let path = std::env::temp_dir().join(format!("chaos-game-socket-{}", std::process::id()));
+    let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+    let reader = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = String::new();
+        std::io::Read::read_to_string(&mut stream, &mut received).unwrap();
+        received
+    });
+    let address = format!("unix://{}", path.to_str().unwrap());
+    let output = chaos_game(&["--iterations", "100", "--output", &address]);
+    assert!(output.status.success());
+
+    let received = reader.join().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(received.lines().count(), 100);
+}
+
+#[test]
+fn output_to_a_closed_port_exits_with_an_error() {
+    // Bound and dropped straight away, so nothing is listening there
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let address = format!("tcp://127.0.0.1:{}", port);
+    let output = chaos_game(&["--iterations", "100", "--output", &address]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("error: couldn't connect to"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn auto_iterations_fits_the_count_to_the_vertices() {
+    // --max-points stops the run early, the count picked is reported up front
+    let output = chaos_game(&["square-one", "--auto-iterations", "--max-points", "10"]);
+    let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+    assert!(
+        stderr.contains("iterations: 1777778 for 4 vertices"),
+        "{}",
+        stderr
+    );
+    assert_eq!(stdout(output).lines().count(), 10);
+
+    let output = chaos_game(&["square-one", "--auto-iterations", "--iterations", "10"]);
+    let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+    assert!(!stderr.contains("vertices"), "{}", stderr);
+    assert_eq!(stdout(output).lines().count(), 10);
+}
+
+#[test]
+fn medial_draws_each_level_as_a_closed_group() {
+    let output = stdout(chaos_game(&["medial", "--medial-depth", "2"]));
+    let levels: Vec<Vec<Vec<f64>>> = output
+        .split("\n\n")
+        .filter(|level| !level.trim().is_empty())
+        .map(|level| {
+            level
+                .lines()
+                .map(|line| line.split(' ').map(|v| v.parse().unwrap()).collect())
+                .collect()
+        })
+        .collect();
+
+    assert_eq!(levels.len(), 3);
+    for (level, rows) in levels.iter().enumerate() {
+        // A triangle closed by repeating its first vertex
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], rows[3]);
+        assert!(rows.iter().all(|row| row[2] == level as f64));
+    }
+}
+
+#[test]
+fn bad_flags_exit_with_an_error() {
+    for (args, message) in &[
+        (
+            &["--iterations", "abc"][..],
+            "abc is not a valid value for --iterations",
+        ),
+        (&["--bogus"], "--bogus is not a recognized flag"),
+        (&["--contains", "1"], "--contains takes x,y"),
+        (&["--threads", "2", "--hull"], "--threads can't be combined"),
+        (&["--split", "2"], "--split needs --output"),
+        (
+            &["--output", "tcp://127.0.0.1:1", "--split", "2"],
+            "--split writes to files, it can't split a socket",
+        ),
+        (
+            &["hilbert", "--order", "40"],
+            "--order has to be less than 32",
+        ),
+        (
+            &["sierpinski-triangle", "extra"],
+            "unexpected argument extra",
+        ),
+        (
+            &["--restrict-expr", "c == 9"],
+            "--restrict-expr turns down every target",
+        ),
+        (
+            &["--output", "/tmp/sx", "--split", "0"],
+            "--split needs at least 1 file",
+        ),
+        (&["--every", "0"], "--every needs at least 1"),
+        (
+            &["--grid-unique", "0"],
+            "--grid-unique needs a cell size more than 0",
+        ),
+        (
+            &["--grid-unique", "-1"],
+            "--grid-unique needs a cell size more than 0",
+        ),
+        (
+            &["square-one", "--start-weights", "1,1"],
+            "--start-weights needs one weight per edge",
+        ),
+        (
+            &["julia", "--start-weights", "1,1"],
+            "--start-weights only applies to the polygon",
+        ),
+        (
+            &["pascal", "--start-weights", "1"],
+            "--start-weights only applies to the polygon",
+        ),
+        (
+            &["julia", "--contains", "0,0"],
+            "--contains only applies to the polygon",
+        ),
+        (
+            &["rose", "--contains", "0,0"],
+            "--contains only applies to the polygon",
+        ),
+        (
+            &["vicsek", "--panels", "julia"],
+            "--panels names the fractals to play, vicsek can't be given as well",
+        ),
+        (
+            &["--panels", "vicsek", "--estimate-memory"],
+            "--panels can't be combined",
+        ),
+        (
+            &[
+                "--emit-selection",
+                "/nonexistent-chaos-game-dir/selection.txt",
+            ],
+            "/nonexistent-chaos-game-dir/selection.txt: ",
+        ),
+        (
+            &["--threads", "2", "--until-coverage", "0.1"],
+            "--threads can't be combined",
+        ),
+        (
+            &["--threads", "2", "--cell-count-check"],
+            "--threads can't be combined",
+        ),
+        (
+            &["--threads", "2", "--verify"],
+            "--threads can't be combined",
+        ),
+        (
+            &["--threads", "2", "--emit-selection", "selection.txt"],
+            "--threads can't be combined",
+        ),
+        (
+            &["--threads", "2", "--meta", "meta.json"],
+            "--threads can't be combined",
+        ),
+        (
+            &["--threads", "2", "--decimate-adaptive", "5"],
+            "--threads can't be combined",
+        ),
+        (
+            &["--tile", "0", "3"],
+            "--tile needs at least 1 row and 1 column",
+        ),
+        (
+            &["--tile", "2", "0"],
+            "--tile needs at least 1 row and 1 column",
+        ),
+        (
+            &["--histogram", "0"],
+            "--histogram needs at least 1 cell across",
+        ),
+        (&["--sig-figs", "0"], "--sig-figs needs at least 1 figure"),
+        (
+            &["--points-per-line", "0"],
+            "--points-per-line needs at least 1 point",
+        ),
+        (
+            &["--flush-every", "0"],
+            "--flush-every needs at least 1 row",
+        ),
+        (
+            &["--rate", "0"],
+            "--rate needs a number of rows a second more than 0",
+        ),
+        (
+            &["--rate", "-5"],
+            "--rate needs a number of rows a second more than 0",
+        ),
+        (
+            &["--rate", "NaN"],
+            "--rate needs a number of rows a second more than 0",
+        ),
+        (
+            &["--quantize-colors", "0"],
+            "--quantize-colors needs at least 1 color",
+        ),
+        (&["--frames", "0"], "--frames needs at least 1 frame"),
+        (
+            &["apollonian", "--depth", "13"],
+            "--depth can be at most 12 for apollonian",
+        ),
+        (
+            &["penrose", "--depth", "15"],
+            "--depth can be at most 14 for penrose",
+        ),
+    ] {
+        let output = chaos_game(args);
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            stderr.contains(&format!("error: {}", message)),
+            "{}",
+            stderr
+        );
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+}
+
+#[test]
+fn unreadable_inputs_exit_with_an_error() {
+    let svg = std::env::temp_dir().join(format!("chaos-game-bad-{}.svg", std::process::id()));
+    std::fs::write(&svg, "<svg><circle r='5'/></svg>").unwrap();
+    let svg = svg.to_str().unwrap();
+    let no_shape = format!("{}: no <polygon> or <path> to read", svg);
+    for (args, message) in &[
+        (
+            vec!["svg-polygon", "--svg-input", "/no/such/shape.svg"],
+            "/no/such/shape.svg: ",
+        ),
+        (vec!["svg-polygon", "--svg-input", svg], no_shape.as_str()),
+        (
+            vec!["ifs", "--config", "/no/such/config"],
+            "/no/such/config: ",
+        ),
+        (
+            vec!["merge", "/no/such/points.txt"],
+            "/no/such/points.txt: ",
+        ),
+    ] {
+        let output = chaos_game(args);
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            stderr.contains(&format!("error: {}", message)),
+            "{}",
+            stderr
+        );
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+    std::fs::remove_file(svg).unwrap();
+}
+
+#[test]
+fn points_per_line_packs_the_same_points() {
+    let args = ["--iterations", "7", "--seed", "200"];
+    let plain = stdout(chaos_game(&args));
+    let packed = stdout(chaos_game(
+        &[&args[..], &["--points-per-line", "3"]].concat(),
+    ));
+
+    let lines: Vec<&str> = packed.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0].split(' ').count(), 6);
+    assert_eq!(lines[2].split(' ').count(), 2);
+    let tokens = |text: &str| {
+        text.split_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(tokens(&packed), tokens(&plain));
+}
+
+#[test]
+fn list_prints_the_fractals() {
+    let list = stdout(chaos_game(&["list"]));
+    let names: Vec<&str> = list.lines().collect();
+    for name in &["sierpinski-triangle", "vicsek", "nflake", "heighway-dragon"] {
+        assert!(names.contains(name), "{} not in {:?}", name, names);
+    }
+}
+
+#[test]
+fn unknown_command_exits_with_an_error() {
+    let output = chaos_game(&["no-such-fractal", "--iterations", "10"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("no-such-fractal is not a fractal"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("chaos-game list"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn describing_an_unknown_fractal_exits_with_an_error() {
+    let output = chaos_game(&["describe", "no-such-fractal"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+/// An `ifs` config that grows without bound, overflowing to infinity within a few hundred points
+fn divergent_config(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "chaos-game-divergent-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::write(&path, "transform = 10, 0, 0, 10, 1, 1, 1\n").unwrap();
+    path
+}
+
+#[test]
+fn non_finite_points_are_skipped_by_default() {
+    let config = divergent_config("skip");
+    let output = chaos_game(&[
+        "ifs",
+        "--config",
+        config.to_str().unwrap(),
+        "--iterations",
+        "1000",
+    ]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows = stdout.lines().count();
+    assert!(0 < rows && rows < 1000, "{} rows", rows);
+    assert!(!stdout.contains("inf") && !stdout.contains("NaN"));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!("skipped {} points", 1000 - rows)),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn non_finite_points_abort_when_asked() {
+    let config = divergent_config("abort");
+    let output = chaos_game(&[
+        "ifs",
+        "--config",
+        config.to_str().unwrap(),
+        "--iterations",
+        "1000",
+        "--non-finite",
+        "abort",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("diverged"), "{}", stderr);
+}